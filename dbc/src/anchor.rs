@@ -0,0 +1,1480 @@
+// BP Core Library implementing LNP/BP specifications & standards related to
+// bitcoin protocol
+//
+// Written in 2020-2022 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the Apache 2.0 License
+// along with this software.
+// If not, see <https://opensource.org/licenses/Apache-2.0>.
+
+//! Anchors: data structures binding [LNPBP-4] multi-protocol commitments to
+//! a specific bitcoin transaction via deterministic bitcoin commitment.
+//!
+//! [LNPBP-4]: https://github.com/LNP-BP/LNPBPs/blob/master/lnpbp-0004.md
+
+use std::fmt::{self, Display, Formatter};
+use std::hash::Hasher;
+use std::io;
+use std::str::FromStr;
+
+use amplify::num::u256;
+use amplify::Wrapper;
+use bitcoin::consensus::{self, Decodable, Encodable, Params};
+use bitcoin::hashes::hex::FromHex;
+use bitcoin::hashes::{sha256, sha256t, Hash};
+use bitcoin::util::merkleblock::{MerkleBlock, PartialMerkleTree};
+use bitcoin::util::uint::Uint256;
+use bitcoin::{secp256k1, BlockHeader, Transaction, Txid};
+use commit_verify::multi_commit::{
+    Message, MultiCommitBlock, MultiCommitItem, MultiCommitment, MultiSource,
+    ProtocolId,
+};
+use commit_verify::{
+    commit_encode, CommitEncode, CommitVerify, ConsensusCommit,
+    EmbedCommitVerify, TaggedHash, TryCommitVerify,
+};
+use lnpbp_bech32::{FromBech32Str, ToBech32String};
+use strict_encoding::StrictEncode;
+
+use crate::{
+    Container, Error, Proof, ScriptEncodeData, ScriptEncodeMethod,
+    TxCommitment, TxContainer, TxSupplement,
+};
+
+/// Protocol tag used for LNPBP-1 tweaking of the public key which holds
+/// LNPBP-4 multi-protocol commitment
+pub const LNPBP4_PROTOCOL_TAG: &str = "LNPBP4";
+
+// "bp:anchor"
+static MIDSTATE_ANCHOR_ID: [u8; 32] = [
+    0x9F, 0x22, 0xFE, 0xFE, 0xCB, 0x36, 0x98, 0xEB, 0x8B, 0xF8, 0x84, 0xA4,
+    0x48, 0xEE, 0x33, 0xF9, 0xE3, 0x10, 0xC9, 0x6D, 0x66, 0x38, 0xAB, 0xC6,
+    0x70, 0x55, 0x6A, 0x5D, 0xF9, 0xC5, 0xE9, 0x97,
+];
+
+/// Tag used for [`AnchorId`] hash type
+pub struct AnchorIdTag;
+
+impl sha256t::Tag for AnchorIdTag {
+    #[inline]
+    fn engine() -> sha256::HashEngine {
+        let midstate = sha256::Midstate::from_inner(MIDSTATE_ANCHOR_ID);
+        sha256::HashEngine::from_midstate(midstate, 64)
+    }
+}
+
+/// Unique anchor identifier equivalent to the anchor commitment hash
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+#[derive(
+    Wrapper, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default, From
+)]
+#[wrapper(
+    Debug, Display, FromStr, LowerHex, Index, IndexRange, IndexFrom, IndexTo,
+    IndexFull
+)]
+pub struct AnchorId(sha256t::Hash<AnchorIdTag>);
+
+impl<M> CommitVerify<M> for AnchorId
+where
+    M: AsRef<[u8]>,
+{
+    #[inline]
+    fn commit(msg: &M) -> AnchorId { AnchorId::hash(msg) }
+}
+
+impl strict_encoding::Strategy for AnchorId {
+    type Strategy = strict_encoding::strategies::Wrapped;
+}
+
+impl commit_encode::Strategy for AnchorId {
+    type Strategy = commit_encode::strategies::UsingStrict;
+}
+
+/// SPV proof of the witness transaction inclusion into a block, represented
+/// by a partial merkle tree in the same form as it is used in bitcoin
+/// `merkleblock` P2P messages
+#[derive(Wrapper, Clone, Debug, From)]
+pub struct SpvProof(PartialMerkleTree);
+
+// Partial merkle tree flag bits are padded to a full byte during
+// serialization, so deserialized trees may not be equal to the original
+// ones. Thus, we compare proofs by their consensus serialization.
+impl PartialEq for SpvProof {
+    fn eq(&self, other: &Self) -> bool {
+        consensus::serialize(&self.0) == consensus::serialize(&other.0)
+    }
+}
+
+impl Eq for SpvProof {}
+
+impl std::hash::Hash for SpvProof {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write(&consensus::serialize(&self.0))
+    }
+}
+
+impl Encodable for SpvProof {
+    #[inline]
+    fn consensus_encode<S: io::Write>(&self, s: S) -> Result<usize, io::Error> {
+        self.0.consensus_encode(s)
+    }
+}
+
+impl Decodable for SpvProof {
+    #[inline]
+    fn consensus_decode<D: io::Read>(
+        d: D,
+    ) -> Result<Self, consensus::encode::Error> {
+        PartialMerkleTree::consensus_decode(d).map(SpvProof)
+    }
+}
+
+// String representation of the proof is the hex encoding of its consensus
+// serialization, as used by `merkleblock` RPC calls of bitcoin core
+impl Display for SpvProof {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&consensus::encode::serialize_hex(&self.0))
+    }
+}
+
+impl FromStr for SpvProof {
+    type Err = consensus::encode::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let data = Vec::<u8>::from_hex(s).map_err(|_| {
+            consensus::encode::Error::ParseFailed("invalid hex encoding")
+        })?;
+        consensus::deserialize(&data)
+    }
+}
+
+impl strict_encoding::Strategy for SpvProof {
+    type Strategy = strict_encoding::strategies::BitcoinConsensus;
+}
+
+#[cfg(feature = "test-utils")]
+impl<'a> arbitrary::Arbitrary<'a> for SpvProof {
+    fn arbitrary(
+        u: &mut arbitrary::Unstructured<'a>,
+    ) -> arbitrary::Result<Self> {
+        // Partial merkle trees can't be constructed for empty blocks
+        let count = u.int_in_range(1..=16)?;
+        let mut txids = Vec::with_capacity(count);
+        let mut matches = Vec::with_capacity(count);
+        for _ in 0..count {
+            txids.push(Txid::from_inner(u.arbitrary()?));
+            matches.push(u.arbitrary()?);
+        }
+        Ok(PartialMerkleTree::from_txids(&txids, &matches).into())
+    }
+}
+
+#[cfg(feature = "serde")]
+crate::serde_str_or_strict!(SpvProof, "hex string of a partial merkle tree");
+
+impl SpvProof {
+    /// Verifies that the proof commits to the transaction `txid` and is
+    /// valid for the block with the given `header`.
+    ///
+    /// NB: The header itself (its proof of work and position in the best
+    /// chain) must be validated by the caller.
+    pub fn verify(&self, txid: Txid, header: &BlockHeader) -> bool {
+        let mut matches = vec![];
+        let mut indexes = vec![];
+        match self.0.extract_matches(&mut matches, &mut indexes) {
+            Ok(merkle_root) => {
+                merkle_root == header.merkle_root && matches.contains(&txid)
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+/// Errors detected during SPV verification of anchors and construction of
+/// [`HeaderChain`]
+#[derive(
+    Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display, Error
+)]
+#[display(doc_comments)]
+pub enum SpvError {
+    /// witness transaction is not proven to be included into any of the
+    /// blocks
+    NotIncluded,
+
+    /// block header at height {0} does not build on the previous header in
+    /// the chain
+    BrokenChain(u32),
+
+    /// block header at height {0} declares proof of work target above the
+    /// limit of the chain
+    TargetAboveLimit(u32),
+
+    /// block header at height {0} changes difficulty outside of a difficulty
+    /// adjustment boundary
+    UnexpectedRetarget(u32),
+
+    /// block header at height {0} adjusts difficulty by a value not allowed
+    /// by the consensus rules
+    InvalidRetarget(u32),
+
+    /// block header at height {0} does not satisfy its proof of work target
+    InvalidPow(u32),
+
+    /// header chain can't be extended above the maximal block height
+    HeightOverflow,
+}
+
+/// Locally maintained continuous chain of block headers, used for offline
+/// SPV verification of anchors with [`Anchor::verify_spv_chain`].
+///
+/// Each header added to the chain must build on the previous one, declare
+/// proof of work target not exceeding the chain `pow_limit` and satisfy it.
+/// Headers may change difficulty only at difficulty adjustment boundaries,
+/// and only to the value required by the consensus rules, which is computed
+/// from the first and the last headers of the previous adjustment period.
+/// If the first header of the period precedes the start of the chain, the
+/// new target is only checked not to exceed four times the previous one,
+/// which is the maximal adjustment allowed by consensus.
+///
+/// Minimum-difficulty blocks, which testnet3 and testnet4 allow after 20
+/// minutes without a block, are rejected.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct HeaderChain {
+    pow_limit: Uint256,
+    retarget_interval: u32,
+    target_timespan: u32,
+    no_retargeting: bool,
+    start_height: u32,
+    headers: Vec<BlockHeader>,
+}
+
+impl HeaderChain {
+    /// Starts header chain from the trusted `header` at a given `height`,
+    /// using proof of work limit and difficulty adjustment rules from the
+    /// consensus `params` of the chain.
+    pub fn with(
+        params: &Params,
+        height: u32,
+        header: BlockHeader,
+    ) -> Result<HeaderChain, SpvError> {
+        Self::check_pow(params.pow_limit, height, &header)?;
+        Ok(HeaderChain {
+            pow_limit: params.pow_limit,
+            retarget_interval: params.difficulty_adjustment_interval() as u32,
+            target_timespan: params.pow_target_timespan as u32,
+            no_retargeting: params.no_pow_retargeting,
+            start_height: height,
+            headers: vec![header],
+        })
+    }
+
+    /// Returns height of the first header in the chain
+    #[inline]
+    pub fn start_height(&self) -> u32 { self.start_height }
+
+    /// Returns height of the last header in the chain
+    #[inline]
+    pub fn tip_height(&self) -> u32 {
+        // `push` guarantees that the expression does not overflow
+        self.start_height + (self.headers.len() as u32 - 1)
+    }
+
+    /// Returns the last header in the chain
+    #[inline]
+    pub fn tip(&self) -> &BlockHeader {
+        self.headers.last().expect("header chain is never empty")
+    }
+
+    /// Returns header at a given `height`, if it is present in the chain
+    pub fn header_at(&self, height: u32) -> Option<&BlockHeader> {
+        height
+            .checked_sub(self.start_height)
+            .and_then(|index| self.headers.get(index as usize))
+    }
+
+    /// Iterates over headers in the chain together with their heights
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &BlockHeader)> {
+        let start_height = self.start_height;
+        self.headers
+            .iter()
+            .enumerate()
+            .map(move |(index, header)| (start_height + index as u32, header))
+    }
+
+    /// Adds `header` to the tip of the chain. The chain is left unchanged
+    /// if the header fails validation.
+    pub fn push(&mut self, header: BlockHeader) -> Result<(), SpvError> {
+        let height = self
+            .tip_height()
+            .checked_add(1)
+            .ok_or(SpvError::HeightOverflow)?;
+        let tip = self.tip();
+        if header.prev_blockhash != tip.block_hash() {
+            return Err(SpvError::BrokenChain(height));
+        }
+        Self::check_pow(self.pow_limit, height, &header)?;
+        if self.no_retargeting || !height.is_multiple_of(self.retarget_interval)
+        {
+            if header.bits != tip.bits {
+                return Err(SpvError::UnexpectedRetarget(height));
+            }
+        } else {
+            self.check_retarget(height, &header)?;
+        }
+        self.headers.push(header);
+        Ok(())
+    }
+
+    /// Adds `headers` to the tip of the chain one by one, stopping at the
+    /// first invalid header
+    pub fn extend(
+        &mut self,
+        headers: impl IntoIterator<Item = BlockHeader>,
+    ) -> Result<(), SpvError> {
+        headers.into_iter().try_for_each(|header| self.push(header))
+    }
+
+    // Follows `CalculateNextWorkRequired` from Bitcoin Core, including its
+    // wrapping 256-bit arithmetic
+    fn check_retarget(
+        &self,
+        height: u32,
+        header: &BlockHeader,
+    ) -> Result<(), SpvError> {
+        let tip = self.tip();
+        let prev_target = tip.target();
+        let first = self.header_at(height - self.retarget_interval);
+        let valid = match first {
+            Some(first) => {
+                let timespan = self.target_timespan as i64;
+                let actual_timespan = (tip.time as i64 - first.time as i64)
+                    .clamp(timespan / 4, timespan * 4);
+                let mut target = prev_target.mul_u32(actual_timespan as u32)
+                    / Uint256::from_u64(timespan as u64)
+                        .expect("u64 fits into Uint256");
+                if target > self.pow_limit {
+                    target = self.pow_limit;
+                }
+                header.bits == BlockHeader::compact_target_from_u256(&target)
+            }
+            None => {
+                let max_target = if prev_target > self.pow_limit >> 2 {
+                    self.pow_limit
+                } else {
+                    prev_target.mul_u32(4)
+                };
+                header.target() <= max_target
+            }
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(SpvError::InvalidRetarget(height))
+        }
+    }
+
+    fn check_pow(
+        pow_limit: Uint256,
+        height: u32,
+        header: &BlockHeader,
+    ) -> Result<(), SpvError> {
+        let target = header.target();
+        if target > pow_limit {
+            return Err(SpvError::TargetAboveLimit(height));
+        }
+        header
+            .validate_pow(&target)
+            .map(|_| ())
+            .map_err(|_| SpvError::InvalidPow(height))
+    }
+}
+
+/// Errors happening during merging of two anchors with
+/// [`Anchor::merge_reveal`]
+#[derive(
+    Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display, Error
+)]
+#[display(doc_comments)]
+pub enum MergeError {
+    /// anchors are related to different witness transactions
+    TxidMismatch,
+
+    /// anchors have different deterministic bitcoin commitment proofs
+    DbcProofMismatch,
+
+    /// anchors contain different LNPBP-4 multi-protocol commitments
+    Lnpbp4Mismatch,
+
+    /// anchors use different entropy values for LNPBP-4 commitment
+    EntropyMismatch,
+}
+
+/// Anchor is a data structure used in client-side-validated protocols to
+/// prove that a message under a given protocol was committed into a bitcoin
+/// transaction. It combines [LNPBP-4] multi-protocol commitment block, which
+/// is placed into the LNPBP-3 transaction output, with the deterministic
+/// bitcoin commitment proof for that output.
+///
+/// String representation of the anchor is a Bech32m encoding of its strict
+/// serialization using `anchor` HRP.
+///
+/// [LNPBP-4]: https://github.com/LNP-BP/LNPBPs/blob/master/lnpbp-0004.md
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Display)]
+#[derive(StrictEncode, StrictDecode)]
+#[display(Anchor::to_bech32_string)]
+pub struct Anchor {
+    /// Id of the transaction containing the commitment
+    pub txid: Txid,
+
+    /// LNPBP-4 multi-protocol commitment block
+    pub lnpbp4_proof: MultiCommitBlock,
+
+    /// Deterministic bitcoin commitment proof for the LNPBP-3 transaction
+    /// output holding the commitment to [`Anchor::lnpbp4_proof`]
+    pub dbc_proof: Proof,
+
+    /// Optional proof of the witness transaction inclusion into a block
+    pub spv_proof: Option<SpvProof>,
+}
+
+// Entropy of the multi-protocol commitment block and SPV proof are not
+// committed to, since they do not participate in the commitment placed into
+// the transaction and may be unknown to some of the anchor holders
+impl CommitEncode for Anchor {
+    fn commit_encode<E: io::Write>(&self, mut e: E) -> usize {
+        let mut len = self
+            .txid
+            .strict_encode(&mut e)
+            .expect("CommitEncode of Txid has failed");
+        len += self
+            .lnpbp4_proof
+            .consensus_commit()
+            .strict_encode(&mut e)
+            .expect("CommitEncode of MultiCommitment has failed");
+        len + self
+            .dbc_proof
+            .strict_encode(&mut e)
+            .expect("CommitEncode of dbc::Proof has failed")
+    }
+}
+
+impl ConsensusCommit for Anchor {
+    type Commitment = AnchorId;
+}
+
+impl lnpbp_bech32::Strategy for Anchor {
+    const HRP: &'static str = "anchor";
+    type Strategy = lnpbp_bech32::strategies::UsingStrictEncoding;
+}
+
+impl FromStr for Anchor {
+    type Err = lnpbp_bech32::Error;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Anchor::from_bech32_str(s)
+    }
+}
+
+// Generated anchors are not guaranteed to follow LNPBP-4 slot placement rules
+// or to commit to any transaction, the same way as anchors decoded from
+// untrusted data
+#[cfg(feature = "test-utils")]
+impl<'a> arbitrary::Arbitrary<'a> for Anchor {
+    fn arbitrary(
+        u: &mut arbitrary::Unstructured<'a>,
+    ) -> arbitrary::Result<Self> {
+        let commitments = u
+            .arbitrary_iter::<(Option<[u8; 32]>, [u8; 32])>()?
+            .map(|item| {
+                item.map(|(protocol, message)| MultiCommitItem {
+                    protocol: protocol.map(ProtocolId::from_inner),
+                    message: Message::from_inner(message),
+                })
+            })
+            .collect::<arbitrary::Result<_>>()?;
+        Ok(Anchor {
+            txid: Txid::from_inner(u.arbitrary()?),
+            lnpbp4_proof: MultiCommitBlock {
+                commitments,
+                entropy: u.arbitrary()?,
+            },
+            dbc_proof: u.arbitrary()?,
+            spv_proof: u.arbitrary()?,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+crate::serde_str_or_strict!(Anchor, "Bech32m string with `anchor` HRP");
+
+impl Anchor {
+    /// Commits to the messages under multiple protocols with a single
+    /// LNPBP-4 commitment embedded into the LNPBP-3 output of the transaction
+    /// `tx`, which pays `fee`.
+    ///
+    /// Returns the transaction with the commitment, transaction container
+    /// holding the tweaking factor required to spend the commitment output
+    /// and the anchor. Since LNPBP-4 commitment blocks can't be concealed,
+    /// the same anchor proves commitment for each of the protocols.
+    ///
+    /// Fails with [`Error::DuplicateProtocol`] if more than one message is
+    /// provided under the same protocol.
+    pub fn commit(
+        messages: impl IntoIterator<Item = (ProtocolId, Message)>,
+        tx: Transaction,
+        fee: u64,
+        pubkey: secp256k1::PublicKey,
+        source: ScriptEncodeData,
+        method: ScriptEncodeMethod,
+    ) -> Result<(TxCommitment, TxContainer, Anchor), Error> {
+        TxContainer::check_outputs(&tx)?;
+        let mut multi_source = MultiSource::default();
+        for (protocol_id, message) in messages {
+            if multi_source.messages.insert(protocol_id, message).is_some() {
+                return Err(Error::DuplicateProtocol(protocol_id));
+            }
+        }
+        let lnpbp4_proof = MultiCommitBlock::try_commit(&multi_source)?;
+
+        let supplement = Self::supplement(fee);
+        let mut container = TxContainer::construct(
+            supplement.protocol_factor,
+            &supplement.tag,
+            fee,
+            tx,
+            pubkey,
+            source,
+            method,
+        );
+        let commitment = TxCommitment::embed_commit(
+            &mut container,
+            &lnpbp4_proof.consensus_commit().into_inner(),
+        )?;
+
+        let anchor = Anchor {
+            txid: commitment.as_inner().txid(),
+            lnpbp4_proof,
+            dbc_proof: container.to_proof(),
+            spv_proof: None,
+        };
+        Ok((commitment, container, anchor))
+    }
+
+    /// Returns [`AnchorId`] of the anchor
+    #[inline]
+    pub fn anchor_id(&self) -> AnchorId { self.consensus_commit() }
+
+    /// Merges two anchors for the same witness transaction, revealing the
+    /// information which is known to one of them only (i.e. LNPBP-4 entropy
+    /// or SPV proof).
+    ///
+    /// Both anchors must commit to the same data, otherwise [`MergeError`] is
+    /// returned.
+    pub fn merge_reveal(mut self, other: Self) -> Result<Self, MergeError> {
+        if self.txid != other.txid {
+            return Err(MergeError::TxidMismatch);
+        }
+        if self.dbc_proof != other.dbc_proof {
+            return Err(MergeError::DbcProofMismatch);
+        }
+        if self.lnpbp4_proof.commitments != other.lnpbp4_proof.commitments {
+            return Err(MergeError::Lnpbp4Mismatch);
+        }
+        self.lnpbp4_proof.entropy =
+            match (self.lnpbp4_proof.entropy, other.lnpbp4_proof.entropy) {
+                (Some(a), Some(b)) if a != b => {
+                    return Err(MergeError::EntropyMismatch)
+                }
+                (a, b) => a.or(b),
+            };
+        self.spv_proof = self.spv_proof.or(other.spv_proof);
+        Ok(self)
+    }
+
+    /// Verifies that the witness transaction is included into the block with
+    /// the provided `header` using the SPV proof embedded into the anchor.
+    /// Returns `false` if the anchor does not contain SPV proof.
+    ///
+    /// NB: The header itself (its proof of work and position in the best
+    /// chain) must be validated by the caller.
+    pub fn verify_spv(&self, header: &BlockHeader) -> bool {
+        self.spv_proof
+            .as_ref()
+            .map(|proof| proof.verify(self.txid, header))
+            .unwrap_or(false)
+    }
+
+    /// Sets SPV proof of the anchor from the `merkle_block` received from a
+    /// bitcoin node, returning the header of the block which must be checked
+    /// against the header chain. Fails with [`SpvError::NotIncluded`],
+    /// leaving the anchor unchanged, if the witness transaction is not among
+    /// the transactions matched by the merkle block.
+    pub fn add_merkle_block(
+        &mut self,
+        merkle_block: &MerkleBlock,
+    ) -> Result<BlockHeader, SpvError> {
+        let proof = SpvProof::from(merkle_block.txn.clone());
+        if !proof.verify(self.txid, &merkle_block.header) {
+            return Err(SpvError::NotIncluded);
+        }
+        self.spv_proof = Some(proof);
+        Ok(merkle_block.header)
+    }
+
+    /// Verifies the anchor against a locally maintained chain of block
+    /// `headers`, returning the number of confirmations the witness
+    /// transaction has in the chain according to the embedded SPV proof.
+    ///
+    /// NB: Whether the header chain belongs to the best known chain must be
+    /// validated by the caller.
+    pub fn verify_spv_chain(
+        &self,
+        headers: &HeaderChain,
+    ) -> Result<u32, SpvError> {
+        headers
+            .iter()
+            .find(|(_, header)| self.verify_spv(header))
+            .map(|(height, _)| headers.tip_height() - height + 1)
+            .ok_or(SpvError::NotIncluded)
+    }
+
+    /// Returns LNPBP-1 tag of the public key holding multi-protocol
+    /// commitment
+    #[inline]
+    pub fn protocol_tag() -> sha256::Hash {
+        sha256::Hash::hash(LNPBP4_PROTOCOL_TAG.as_bytes())
+    }
+
+    /// Constructs LNPBP-3 transaction supplement for the anchor commitment.
+    ///
+    /// All protocols committed through an anchor share the same transaction
+    /// output, so the protocol factor does not depend on a particular
+    /// protocol and is derived from the first four bytes of
+    /// [`Anchor::protocol_tag`].
+    pub fn supplement(fee: u64) -> TxSupplement {
+        let tag = Self::protocol_tag();
+        let mut factor = [0u8; 4];
+        factor.copy_from_slice(&tag[..4]);
+        TxSupplement {
+            protocol_factor: u32::from_le_bytes(factor),
+            fee,
+            tag,
+        }
+    }
+
+    /// Returns multi-protocol commitment which must be embedded into the
+    /// transaction
+    #[inline]
+    pub fn lnpbp4_commitment(&self) -> MultiCommitment {
+        self.lnpbp4_proof.consensus_commit()
+    }
+
+    /// Checks whether the anchor contains commitment to the `message` under
+    /// the given protocol without verifying the transaction.
+    ///
+    /// According to LNPBP-4 the commitment must be placed into the slot with
+    /// index equal to the protocol id (as a little-endian 256-bit number)
+    /// modulo the number of slots, and the protocol id must not appear in any
+    /// other slot; otherwise a protocol could be committed to several
+    /// messages at once.
+    pub fn contains(&self, protocol_id: ProtocolId, message: Message) -> bool {
+        let commitments = &self.lnpbp4_proof.commitments;
+        if commitments.is_empty() {
+            return false;
+        }
+        let index = (u256::from_le_bytes(protocol_id.into_inner())
+            % u256::from(commitments.len() as u64))
+        .low_u64() as usize;
+        commitments[index] == MultiCommitItem::new(protocol_id, message)
+            && commitments
+                .iter()
+                .filter(|item| item.protocol == Some(protocol_id))
+                .count()
+                == 1
+    }
+
+    /// Verifies that the `message` under the protocol `protocol_id` is
+    /// committed into the transaction `tx` by this anchor.
+    ///
+    /// The `fee` is the fee paid by the transaction, which is required to
+    /// detect LNPBP-3 commitment output; it must be computed by the caller
+    /// from the resolved transaction inputs.
+    pub fn verify(
+        &self,
+        protocol_id: ProtocolId,
+        message: Message,
+        tx: &Transaction,
+        fee: u64,
+    ) -> Result<bool, Error> {
+        if tx.txid() != self.txid || !self.contains(protocol_id, message) {
+            return Ok(false);
+        }
+        // Transactions with no or too many outputs can't contain the
+        // commitment
+        if TxContainer::check_outputs(tx).is_err() {
+            return Ok(false);
+        }
+        let container = TxContainer::reconstruct(
+            &self.dbc_proof,
+            &Self::supplement(fee),
+            tx,
+        )?;
+        TxCommitment::from(tx.clone())
+            .verify(&container, &self.lnpbp4_commitment().into_inner())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use bitcoin::hashes::sha256t::Tag;
+    use bitcoin::hashes::HashEngine;
+    use bitcoin::{Network, Script, TxOut};
+    #[cfg(feature = "test-utils")]
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::lnpbp1::test_helpers::gen_secp_pubkeys;
+    #[cfg(feature = "test-utils")]
+    use crate::test_utils::strategy;
+    use crate::{ScriptEncodeData, ScriptEncodeMethod};
+
+    fn anchored_tx(fee: u64) -> (Anchor, Transaction) {
+        let tx = Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![],
+            output: vec![
+                TxOut {
+                    value: 10_000,
+                    script_pubkey: Script::default(),
+                };
+                3
+            ],
+        };
+        let pubkey = gen_secp_pubkeys(1)[0];
+        let lnpbp4_proof = MultiCommitBlock {
+            commitments: vec![
+                MultiCommitItem {
+                    protocol: None,
+                    message: Message::hash(b"placeholder"),
+                },
+                MultiCommitItem::new(
+                    ProtocolId::from_inner([2u8; 32]),
+                    Message::hash(b"second message"),
+                ),
+                MultiCommitItem::new(
+                    ProtocolId::from_inner([1u8; 32]),
+                    Message::hash(b"first message"),
+                ),
+            ],
+            entropy: Some(0x3F0C),
+        };
+
+        let supplement = Anchor::supplement(fee);
+        let mut container = TxContainer::construct(
+            supplement.protocol_factor,
+            &supplement.tag,
+            fee,
+            tx,
+            pubkey,
+            ScriptEncodeData::SinglePubkey,
+            ScriptEncodeMethod::WPubkeyHash,
+        );
+        let tx = TxCommitment::embed_commit(
+            &mut container,
+            &lnpbp4_proof.consensus_commit().into_inner(),
+        )
+        .unwrap()
+        .into_inner();
+
+        let anchor = Anchor {
+            txid: tx.txid(),
+            lnpbp4_proof,
+            dbc_proof: container.into_proof(),
+            spv_proof: None,
+        };
+        (anchor, tx)
+    }
+
+    #[test]
+    fn test_anchor_id_midstate() {
+        let tag_hash = sha256::Hash::hash(b"bp:anchor");
+        let mut engine = sha256::HashEngine::default();
+        engine.input(&tag_hash[..]);
+        engine.input(&tag_hash[..]);
+        assert_eq!(AnchorIdTag::engine().midstate(), engine.midstate());
+    }
+
+    #[test]
+    fn test_anchor_verify() {
+        let (anchor, tx) = anchored_tx(1000);
+        let first = ProtocolId::from_inner([1u8; 32]);
+        let second = ProtocolId::from_inner([2u8; 32]);
+
+        assert!(anchor
+            .verify(first, Message::hash(b"first message"), &tx, 1000)
+            .unwrap());
+        assert!(anchor
+            .verify(second, Message::hash(b"second message"), &tx, 1000)
+            .unwrap());
+
+        // Message under a different protocol
+        assert!(!anchor
+            .verify(second, Message::hash(b"first message"), &tx, 1000)
+            .unwrap());
+        // Wrong fee points to a different output
+        assert!(!anchor
+            .verify(first, Message::hash(b"first message"), &tx, 1001)
+            .unwrap());
+        // Different transaction
+        let mut other = tx.clone();
+        other.lock_time = 1;
+        assert!(!anchor
+            .verify(first, Message::hash(b"first message"), &other, 1000)
+            .unwrap());
+    }
+
+    #[test]
+    fn test_anchor_contains_placement() {
+        let (mut anchor, _) = anchored_tx(1000);
+        let first = ProtocolId::from_inner([1u8; 32]);
+        let second = ProtocolId::from_inner([2u8; 32]);
+        assert!(anchor.contains(first, Message::hash(b"first message")));
+        assert!(anchor.contains(second, Message::hash(b"second message")));
+
+        // Protocol committed to several messages
+        let messages = [b"A", b"B", b"C"].map(|msg| Message::hash(msg));
+        anchor.lnpbp4_proof.commitments = messages
+            .iter()
+            .map(|message| MultiCommitItem::new(first, *message))
+            .collect();
+        for message in messages {
+            assert!(!anchor.contains(first, message));
+        }
+
+        // Protocol placed into a wrong slot
+        anchor.lnpbp4_proof.commitments = vec![
+            MultiCommitItem::new(second, messages[1]),
+            MultiCommitItem::new(first, messages[0]),
+            MultiCommitItem {
+                protocol: None,
+                message: messages[2],
+            },
+        ];
+        assert!(!anchor.contains(first, messages[0]));
+        assert!(!anchor.contains(second, messages[1]));
+
+        anchor.lnpbp4_proof.commitments = vec![];
+        assert!(!anchor.contains(first, messages[0]));
+    }
+
+    #[test]
+    fn test_anchor_id() {
+        let (anchor, _) = anchored_tx(1000);
+        assert_eq!(anchor.anchor_id(), anchor.consensus_commit());
+
+        // Entropy does not affect anchor id
+        let mut concealed = anchor.clone();
+        concealed.lnpbp4_proof.entropy = None;
+        assert_eq!(anchor.anchor_id(), concealed.anchor_id());
+
+        let (other, _) = anchored_tx(1001);
+        assert_ne!(anchor.anchor_id(), other.anchor_id());
+
+        let id = anchor.anchor_id();
+        assert_eq!(AnchorId::from_str(&id.to_string()), Ok(id));
+    }
+
+    #[test]
+    fn test_anchor_merge_reveal() {
+        let (anchor, _) = anchored_tx(1000);
+        let mut concealed = anchor.clone();
+        concealed.lnpbp4_proof.entropy = None;
+
+        assert_eq!(
+            concealed.clone().merge_reveal(anchor.clone()),
+            Ok(anchor.clone())
+        );
+        assert_eq!(
+            anchor.clone().merge_reveal(concealed.clone()),
+            Ok(anchor.clone())
+        );
+        assert_eq!(
+            concealed.clone().merge_reveal(concealed.clone()),
+            Ok(concealed)
+        );
+
+        let mut other = anchor.clone();
+        other.lnpbp4_proof.entropy = Some(0);
+        assert_eq!(
+            anchor.clone().merge_reveal(other),
+            Err(MergeError::EntropyMismatch)
+        );
+
+        let mut other = anchor.clone();
+        other.lnpbp4_proof.commitments.pop();
+        assert_eq!(
+            anchor.clone().merge_reveal(other),
+            Err(MergeError::Lnpbp4Mismatch)
+        );
+
+        let (other, _) = anchored_tx(1001);
+        assert_eq!(
+            anchor.clone().merge_reveal(other.clone()),
+            Err(MergeError::TxidMismatch)
+        );
+
+        let mut other = anchor.clone();
+        other.dbc_proof.source = ScriptEncodeData::Taproot(Default::default());
+        assert_eq!(
+            anchor.merge_reveal(other),
+            Err(MergeError::DbcProofMismatch)
+        );
+    }
+
+    #[test]
+    fn test_anchor_spv() {
+        let (mut anchor, _) = anchored_tx(1000);
+        let coinbase = Txid::hash(b"coinbase");
+        let other = Txid::hash(b"other");
+        let txids = [coinbase, anchor.txid, other];
+
+        let tree = PartialMerkleTree::from_txids(&txids, &[false, true, false]);
+        let merkle_root =
+            tree.extract_matches(&mut vec![], &mut vec![]).unwrap();
+        let header = BlockHeader {
+            version: 1,
+            prev_blockhash: Default::default(),
+            merkle_root,
+            time: 0,
+            bits: 0,
+            nonce: 0,
+        };
+
+        assert!(!anchor.verify_spv(&header));
+
+        anchor.spv_proof = Some(tree.into());
+        assert!(anchor.verify_spv(&header));
+        let wrong_header = BlockHeader {
+            merkle_root: Default::default(),
+            ..header
+        };
+        assert!(!anchor.verify_spv(&wrong_header));
+
+        // Proof not matching the witness transaction
+        let tree = PartialMerkleTree::from_txids(&txids, &[false, false, true]);
+        anchor.spv_proof = Some(tree.into());
+        assert!(!anchor.verify_spv(&header));
+
+        // SPV proof does not affect anchor id
+        let mut stripped = anchor.clone();
+        stripped.spv_proof = None;
+        assert_eq!(stripped.anchor_id(), anchor.anchor_id());
+        assert_eq!(stripped.merge_reveal(anchor.clone()), Ok(anchor.clone()));
+
+        let data = strict_encoding::strict_serialize(&anchor).unwrap();
+        let decoded: Anchor =
+            strict_encoding::strict_deserialize(&data).unwrap();
+        assert_eq!(decoded, anchor);
+
+        let spv_proof = anchor.spv_proof.unwrap();
+        assert_eq!(
+            SpvProof::from_str(&spv_proof.to_string()).unwrap(),
+            spv_proof
+        );
+        assert!(SpvProof::from_str("not a hex").is_err());
+        assert!(SpvProof::from_str("00").is_err());
+    }
+
+    #[test]
+    fn test_anchor_merkle_block() {
+        let (mut anchor, _) = anchored_tx(1000);
+        let txids =
+            [Txid::hash(b"coinbase"), anchor.txid, Txid::hash(b"other")];
+        let tree = PartialMerkleTree::from_txids(&txids, &[true, true, true]);
+        let header = BlockHeader {
+            version: 1,
+            prev_blockhash: Default::default(),
+            merkle_root: tree
+                .extract_matches(&mut vec![], &mut vec![])
+                .unwrap(),
+            time: 0,
+            bits: 0,
+            nonce: 0,
+        };
+
+        let other = MerkleBlock::from_header_txids_with_predicate(
+            &header,
+            &txids,
+            |txid| *txid == txids[2],
+        );
+        assert_eq!(anchor.add_merkle_block(&other), Err(SpvError::NotIncluded));
+        assert_eq!(anchor.spv_proof, None);
+
+        let merkle_block = MerkleBlock::from_header_txids_with_predicate(
+            &header,
+            &txids,
+            |txid| *txid == anchor.txid,
+        );
+        assert_eq!(anchor.add_merkle_block(&merkle_block), Ok(header));
+        assert!(anchor.verify_spv(&header));
+    }
+
+    fn mine(mut header: BlockHeader) -> BlockHeader {
+        while header.validate_pow(&header.target()).is_err() {
+            header.nonce += 1;
+        }
+        header
+    }
+
+    fn next_block(prev: &BlockHeader, bits: u32) -> BlockHeader {
+        mine(BlockHeader {
+            prev_blockhash: prev.block_hash(),
+            merkle_root: Default::default(),
+            time: prev.time + 1,
+            bits,
+            nonce: 0,
+            ..*prev
+        })
+    }
+
+    // Regtest with retargeting each 4 blocks, one second apart
+    fn retarget_params() -> Params {
+        let mut params = Params::new(Network::Regtest);
+        params.pow_target_spacing = 1;
+        params.pow_target_timespan = 4;
+        params.no_pow_retargeting = false;
+        params
+    }
+
+    #[test]
+    fn test_header_chain() {
+        let params = retarget_params();
+        let start = mine(BlockHeader {
+            version: 1,
+            prev_blockhash: Default::default(),
+            merkle_root: Default::default(),
+            time: 0,
+            bits: 0x2000ffff,
+            nonce: 0,
+        });
+        let mut chain = HeaderChain::with(&params, 2, start).unwrap();
+        let second = next_block(&start, 0x2000ffff);
+        chain.push(second).unwrap();
+        assert_eq!(chain.start_height(), 2);
+        assert_eq!(chain.tip_height(), 3);
+        assert_eq!(chain.tip(), &second);
+        assert_eq!(chain.header_at(2), Some(&start));
+        assert_eq!(chain.header_at(1), None);
+        assert_eq!(chain.header_at(4), None);
+
+        // Header not building on the tip
+        assert_eq!(
+            chain.push(next_block(&start, 0x2000ffff)),
+            Err(SpvError::BrokenChain(4))
+        );
+        // Trivial difficulty above the chain limit
+        assert_eq!(
+            chain.push(next_block(&second, 0x2100ffff)),
+            Err(SpvError::TargetAboveLimit(4))
+        );
+        // Header not satisfying its target
+        let mut unmined = next_block(&second, 0x2000ffff);
+        unmined.bits = 0x1d00ffff;
+        assert_eq!(chain.push(unmined), Err(SpvError::InvalidPow(4)));
+        assert_eq!(chain.tip_height(), 3);
+
+        // The first header of the adjustment period is unknown, so only the
+        // fourfold adjustment limit is checked
+        assert_eq!(
+            chain.push(next_block(&second, 0x2003fffd)),
+            Err(SpvError::InvalidRetarget(4))
+        );
+        assert!(chain.clone().push(next_block(&second, 0x2003fffc)).is_ok());
+        let boundary = next_block(&second, 0x2000ffff);
+        chain.push(boundary).unwrap();
+
+        // Difficulty may change only at the adjustment boundary
+        assert_eq!(
+            chain.push(next_block(&boundary, 0x2000fffe)),
+            Err(SpvError::UnexpectedRetarget(5))
+        );
+        let mut tip = boundary;
+        for _ in 5..8 {
+            tip = next_block(&tip, 0x2000ffff);
+            chain.push(tip).unwrap();
+        }
+
+        // Blocks 4 to 7 took 3 seconds instead of 4, so the target is
+        // decreased to 3/4 of the previous one
+        for bits in [0x2000ffff, 0x2000bffe, 0x2003fffc] {
+            assert_eq!(
+                chain.push(next_block(&tip, bits)),
+                Err(SpvError::InvalidRetarget(8))
+            );
+        }
+        chain.push(next_block(&tip, 0x2000bfff)).unwrap();
+        assert_eq!(chain.tip_height(), 8);
+
+        // Heights can't overflow
+        let mut chain =
+            HeaderChain::with(&params, u32::MAX - 1, start).unwrap();
+        chain.push(second).unwrap();
+        assert_eq!(chain.tip_height(), u32::MAX);
+        assert_eq!(chain.header_at(u32::MAX), Some(&second));
+        assert_eq!(chain.iter().last(), Some((u32::MAX, &second)));
+        assert_eq!(
+            chain.push(next_block(&second, 0x2000ffff)),
+            Err(SpvError::HeightOverflow)
+        );
+        assert_eq!(chain.tip_height(), u32::MAX);
+
+        // Regtest does not retarget at all
+        let regtest = Params::new(Network::Regtest);
+        let mut chain = HeaderChain::with(&regtest, 2014, start).unwrap();
+        chain.push(second).unwrap();
+        assert_eq!(
+            chain.push(next_block(&second, 0x2000fffe)),
+            Err(SpvError::UnexpectedRetarget(2016))
+        );
+        chain.push(next_block(&second, 0x2000ffff)).unwrap();
+
+        // Regtest headers are above mainnet limit
+        let mainnet = Params::new(Network::Bitcoin);
+        assert_eq!(
+            HeaderChain::with(&mainnet, 0, start),
+            Err(SpvError::TargetAboveLimit(0))
+        );
+        let trivial = mine(BlockHeader {
+            bits: 0x22000001,
+            ..start
+        });
+        assert_eq!(
+            HeaderChain::with(&mainnet, 0, trivial),
+            Err(SpvError::TargetAboveLimit(0))
+        );
+    }
+
+    #[test]
+    fn test_header_chain_min_difficulty_attack() {
+        // Mainnet headers can't be mined in tests, thus the retarget check
+        // is applied directly to a chain started from a trusted header
+        let mainnet = Params::new(Network::Bitcoin);
+        let trusted = BlockHeader {
+            version: 1,
+            prev_blockhash: Default::default(),
+            merkle_root: Default::default(),
+            time: 0,
+            bits: 0x1b0404cb,
+            nonce: 0,
+        };
+        let chain = HeaderChain {
+            pow_limit: mainnet.pow_limit,
+            retarget_interval: 2016,
+            target_timespan: mainnet.pow_target_timespan as u32,
+            no_retargeting: false,
+            start_height: 2015,
+            headers: vec![trusted],
+        };
+
+        let fake = BlockHeader {
+            prev_blockhash: trusted.block_hash(),
+            bits: 0x1d00ffff,
+            ..trusted
+        };
+        assert_eq!(
+            chain.check_retarget(2016, &fake),
+            Err(SpvError::InvalidRetarget(2016))
+        );
+
+        let max_target = trusted.target().mul_u32(4);
+        let adjusted = BlockHeader {
+            bits: BlockHeader::compact_target_from_u256(&max_target),
+            ..fake
+        };
+        assert_eq!(chain.check_retarget(2016, &adjusted), Ok(()));
+    }
+
+    #[test]
+    fn test_anchor_spv_chain() {
+        let (mut anchor, _) = anchored_tx(1000);
+        let txids = [Txid::hash(b"coinbase"), anchor.txid];
+        let tree = PartialMerkleTree::from_txids(&txids, &[false, true]);
+        let merkle_root =
+            tree.extract_matches(&mut vec![], &mut vec![]).unwrap();
+        anchor.spv_proof = Some(tree.into());
+
+        let regtest = Params::new(Network::Regtest);
+        let genesis = mine(BlockHeader {
+            version: 1,
+            prev_blockhash: Default::default(),
+            merkle_root: Default::default(),
+            time: 0,
+            bits: 0x207fffff,
+            nonce: 0,
+        });
+        let block = mine(BlockHeader {
+            merkle_root,
+            ..next_block(&genesis, 0x207fffff)
+        });
+
+        let mut chain = HeaderChain::with(&regtest, 0, genesis).unwrap();
+        assert_eq!(anchor.verify_spv_chain(&chain), Err(SpvError::NotIncluded));
+
+        chain.push(block).unwrap();
+        assert_eq!(anchor.verify_spv_chain(&chain), Ok(1));
+
+        let next = next_block(&block, 0x207fffff);
+        chain
+            .extend(vec![next, next_block(&next, 0x207fffff)])
+            .unwrap();
+        assert_eq!(anchor.verify_spv_chain(&chain), Ok(3));
+
+        anchor.spv_proof = None;
+        assert_eq!(anchor.verify_spv_chain(&chain), Err(SpvError::NotIncluded));
+    }
+
+    #[test]
+    fn test_anchor_bech32() {
+        let (anchor, _) = anchored_tx(1000);
+        let s = anchor.to_string();
+        assert!(s.starts_with("anchor1"));
+        assert_eq!(s, anchor.to_bech32_string());
+        assert_eq!(Anchor::from_str(&s), Ok(anchor));
+
+        assert!(Anchor::from_str(&s.replacen("anchor1", "utxob1", 1)).is_err());
+    }
+
+    #[test]
+    fn test_anchor_strict_encoding() {
+        let anchor = Anchor {
+            txid: Txid::hash(b"witness"),
+            lnpbp4_proof: MultiCommitBlock {
+                commitments: vec![
+                    MultiCommitItem {
+                        protocol: None,
+                        message: Message::hash(b"placeholder"),
+                    },
+                    MultiCommitItem::new(
+                        ProtocolId::from_inner([1u8; 32]),
+                        Message::hash(b"message"),
+                    ),
+                ],
+                entropy: Some(0x3F0C),
+            },
+            dbc_proof: Proof {
+                pubkey: gen_secp_pubkeys(1)[0],
+                source: ScriptEncodeData::Taproot(sha256::Hash::hash(
+                    b"script root",
+                )),
+            },
+            spv_proof: None,
+        };
+
+        let data = Vec::<u8>::from_hex(concat!(
+            // txid
+            "81961dbd89a6edf75ed3d94f6393d14ef4258ee45701986e978269eb624c81ee",
+            // number of LNPBP-4 commitment items
+            "0200",
+            // placeholder item: no protocol id, message
+            "00",
+            "4097889236a2af26c293033feb964c4cf118c0224e0d063fec0a89e9d0569ef2",
+            // protocol item: protocol id, message
+            "01",
+            "0101010101010101010101010101010101010101010101010101010101010101",
+            "ab530a13e45914982b79f9b7e3fba994cfd1f3fb22f71cea1afbf02b460c6d1d",
+            // entropy
+            "01",
+            "0c3f000000000000",
+            // DBC proof: original public key
+            "028c28a97bf8298bc0d23d8c749452a32e694b65e30a9472a3954ab30fe5324caa",
+            // DBC proof: taproot script root
+            "02",
+            "4931e89b9da1e1438427089d9f96186f7a936fd40f3e11a60caa3baeb47144db",
+            // no SPV proof
+            "00",
+        ))
+        .unwrap();
+
+        assert_eq!(strict_encoding::strict_serialize(&anchor).unwrap(), data);
+        assert_eq!(
+            strict_encoding::strict_deserialize::<Anchor>(&data).unwrap(),
+            anchor
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_anchor_serde() {
+        let (mut anchor, _) = anchored_tx(1000);
+        let txids = [Txid::hash(b"coinbase"), anchor.txid];
+        let spv_proof =
+            SpvProof::from(PartialMerkleTree::from_txids(&txids, &[
+                false, true,
+            ]));
+
+        let json = serde_json::to_string(&spv_proof).unwrap();
+        assert_eq!(
+            json,
+            format!("\"{}\"", consensus::encode::serialize_hex(&spv_proof.0))
+        );
+        assert_eq!(serde_json::from_str::<SpvProof>(&json).unwrap(), spv_proof);
+        let data = bincode::serialize(&spv_proof).unwrap();
+        assert_eq!(
+            bincode::deserialize::<Vec<u8>>(&data).unwrap(),
+            strict_encoding::strict_serialize(&spv_proof).unwrap()
+        );
+        assert_eq!(bincode::deserialize::<SpvProof>(&data).unwrap(), spv_proof);
+
+        anchor.spv_proof = Some(spv_proof);
+        let json = serde_json::to_string(&anchor).unwrap();
+        assert_eq!(json, format!("\"{}\"", anchor.to_bech32_string()));
+        assert_eq!(serde_json::from_str::<Anchor>(&json).unwrap(), anchor);
+        let data = bincode::serialize(&anchor).unwrap();
+        assert_eq!(
+            bincode::deserialize::<Vec<u8>>(&data).unwrap(),
+            strict_encoding::strict_serialize(&anchor).unwrap()
+        );
+        assert_eq!(bincode::deserialize::<Anchor>(&data).unwrap(), anchor);
+    }
+
+    #[cfg(feature = "test-utils")]
+    proptest! {
+        #[test]
+        fn test_anchor_arbitrary(anchor in strategy::<Anchor>()) {
+            let data = strict_encoding::strict_serialize(&anchor).unwrap();
+            prop_assert_eq!(
+                strict_encoding::strict_deserialize::<Anchor>(&data).unwrap(),
+                anchor.clone()
+            );
+            prop_assert_eq!(
+                Anchor::from_str(&anchor.to_string()),
+                Ok(anchor)
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "test-utils")]
+    fn test_anchor_arbitrary_exhausted() {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        // Exhausted input yields zero bytes, which are not a valid secret key
+        let anchor = Anchor::arbitrary(&mut Unstructured::new(&[])).unwrap();
+        assert_eq!(
+            anchor.dbc_proof.pubkey,
+            secp256k1::PublicKey::from_secret_key(
+                secp256k1::SECP256K1,
+                &secp256k1::key::ONE_KEY
+            )
+        );
+    }
+
+    #[test]
+    fn test_anchor_commit() {
+        let (_, tx) = anchored_tx(1000);
+        let pubkey = gen_secp_pubkeys(1)[0];
+        let messages = vec![
+            (ProtocolId::from_inner([1u8; 32]), Message::hash(b"first")),
+            (ProtocolId::from_inner([2u8; 32]), Message::hash(b"second")),
+            (ProtocolId::from_inner([3u8; 32]), Message::hash(b"third")),
+            (ProtocolId::from_inner([4u8; 32]), Message::hash(b"fourth")),
+        ];
+
+        let (commitment, container, anchor) = Anchor::commit(
+            messages.clone(),
+            tx,
+            1000,
+            pubkey,
+            ScriptEncodeData::SinglePubkey,
+            ScriptEncodeMethod::WPubkeyHash,
+        )
+        .unwrap();
+        assert!(container.tweaking_factor.is_some());
+
+        let tx = commitment.into_inner();
+        for (protocol_id, message) in messages {
+            assert!(anchor.verify(protocol_id, message, &tx, 1000).unwrap());
+        }
+        assert!(!anchor
+            .verify(
+                ProtocolId::from_inner([5u8; 32]),
+                Message::hash(b"fifth"),
+                &tx,
+                1000
+            )
+            .unwrap());
+
+        let (_, tx) = anchored_tx(1000);
+        let protocol_id = ProtocolId::from_inner([1u8; 32]);
+        assert_eq!(
+            Anchor::commit(
+                vec![
+                    (protocol_id, Message::hash(b"first")),
+                    (
+                        ProtocolId::from_inner([2u8; 32]),
+                        Message::hash(b"other")
+                    ),
+                    (protocol_id, Message::hash(b"second")),
+                ],
+                tx,
+                1000,
+                pubkey,
+                ScriptEncodeData::SinglePubkey,
+                ScriptEncodeMethod::WPubkeyHash,
+            )
+            .unwrap_err(),
+            Error::DuplicateProtocol(protocol_id)
+        );
+    }
+
+    #[test]
+    fn test_anchor_output_count() {
+        let pubkey = gen_secp_pubkeys(1)[0];
+        let protocol_id = ProtocolId::from_inner([1u8; 32]);
+        let message = Message::hash(b"message");
+
+        for nouts in [0usize, u16::MAX as usize + 1] {
+            let tx = Transaction {
+                version: 2,
+                lock_time: 0,
+                input: vec![],
+                output: vec![TxOut::default(); nouts],
+            };
+            assert_eq!(
+                Anchor::commit(
+                    vec![(protocol_id, message)],
+                    tx.clone(),
+                    1000,
+                    pubkey,
+                    ScriptEncodeData::SinglePubkey,
+                    ScriptEncodeMethod::WPubkeyHash,
+                )
+                .unwrap_err(),
+                Error::InvalidOutputCount(nouts)
+            );
+
+            let (mut anchor, _) = anchored_tx(1000);
+            anchor.txid = tx.txid();
+            anchor.lnpbp4_proof.commitments =
+                vec![MultiCommitItem::new(protocol_id, message)];
+            assert_eq!(
+                anchor.verify(protocol_id, message, &tx, 1000),
+                Ok(false)
+            );
+        }
+    }
+}
@@ -13,6 +13,8 @@
 // along with this software.
 // If not, see <https://opensource.org/licenses/Apache-2.0>.
 
+use commit_verify::multi_commit;
+
 use crate::lnpbp1;
 
 /// Different error types which may happen during deterministic bitcoin
@@ -65,6 +67,24 @@ pub enum Error {
 
     /// Deterministic bitcoin commitments require use of compressed public keys
     UncompressedKey,
+
+    /// LNPBP-4 multi-protocol commitment has failed: {0}
+    #[from]
+    Lnpbp4(multi_commit::Error),
+
+    /// LNPBP-4 multi-protocol commitment may contain only a single message
+    /// under each protocol, while multiple messages were provided for the
+    /// protocol {0}
+    DuplicateProtocol(multi_commit::ProtocolId),
+
+    /// Taproot tweak of the intermediate key with the tapscript merkle root
+    /// has resulted in an invalid output key. You may try with a different
+    /// source message or public keys.
+    TaprootTweak,
+
+    /// Deterministic bitcoin commitments require transaction to have from 1
+    /// to 65535 outputs, while the transaction has {0} outputs
+    InvalidOutputCount(usize),
 }
 
 impl From<descriptors::Error> for Error {
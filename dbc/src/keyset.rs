@@ -30,6 +30,11 @@ use crate::lnpbp1;
 /// must be hashed during commitment process. Here we use pre-hashed version
 /// of the tag in order to maximize performance for multiple commitments.
 #[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
 pub struct KeysetContainer {
     /// The original public key: host for the commitment
     pub pubkey: secp256k1::PublicKey,
@@ -98,6 +103,11 @@ impl Container for KeysetContainer {
 #[derive(Wrapper, Clone, Copy, PartialEq, Eq, Hash, Debug, Display, From)]
 #[display("{0}", alt = "{_0:#}*")]
 #[wrapper(FromStr, LowerHex)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
 pub struct KeysetCommitment(secp256k1::PublicKey);
 
 impl<MSG> EmbedCommitVerify<MSG> for KeysetCommitment
@@ -184,7 +194,7 @@ mod test {
             "0218845781f631c48f1c9709e23092067d06837f30aa0cd0544ac887fe91ddd166",
         )
         .unwrap();
-        (1..9).into_iter().for_each(|n_keys| {
+        (1..9).for_each(|n_keys| {
             embed_commit_verify_suite::<Vec<u8>, KeysetCommitment>(
                 gen_messages(),
                 &mut KeysetContainer {
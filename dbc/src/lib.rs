@@ -16,6 +16,8 @@
 // Coding conventions
 #![recursion_limit = "256"]
 #![deny(dead_code, /* missing_docs, */ warnings)]
+// Required by the code generated with `strict_encoding` derive macros
+#![allow(clippy::init_numbered_fields)]
 
 #[macro_use]
 extern crate amplify;
@@ -25,17 +27,23 @@ extern crate strict_encoding;
 #[macro_use]
 extern crate serde_crate as serde;
 
+pub mod anchor;
 mod error;
 pub mod keyset;
 pub mod lnpbp1;
 pub mod lockscript;
 pub mod pubkey;
+#[cfg(feature = "serde")]
+pub mod serde_utils;
 pub mod spk;
 pub mod taproot;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
 pub mod tx;
 pub mod txout;
 pub mod types;
 
+pub use anchor::{Anchor, AnchorId, HeaderChain, SpvError, SpvProof};
 pub use error::Error;
 pub use keyset::{KeysetCommitment, KeysetContainer};
 pub use lockscript::{LockscriptCommitment, LockscriptContainer};
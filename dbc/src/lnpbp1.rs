@@ -66,6 +66,7 @@ pub enum Error {
 /// - `target_pubkey`, with a tweaked version of the public key containing
 ///   commitment to the message and the rest of keyset,
 /// - `keyset`, by replacing original `target_pubkey` with its tweaked version
+///
 /// and returns `tweaking_factor` as a return parameter wrapped into
 /// [`Result::Ok`].
 ///
@@ -89,7 +90,6 @@ pub enum Error {
 ///
 /// Please refer to the original document for the verification:
 /// <https://github.com/LNP-BP/LNPBPs/blob/master/lnpbp-0001.md>
-
 // #[consensus_critical("RGB")]
 // #[standard_critical("LNPBP-1")]
 pub fn commit(
@@ -213,6 +213,27 @@ pub fn verify(
     }
 }
 
+/// Applies LNPBP-1 tweaking factor, returned by [`commit()`], to the secret key
+/// corresponding to the original `target_pubkey`, so the output containing
+/// the tweaked public key can be spent.
+///
+/// Taproot outputs additionally require BIP-341 tweak of the key; use
+/// [`crate::taproot::tweak_key_pair`] for them.
+///
+/// # Errors
+///
+/// Fails with [`Error::InvalidTweak`] if the tweaking factor is outside of
+/// Secp256k1 order `n` or the tweaked key is zero; in this case the value of
+/// `secret_key` is undefined and must be discarded.
+pub fn tweak_secret_key(
+    secret_key: &mut secp256k1::SecretKey,
+    tweaking_factor: &Hmac<sha256::Hash>,
+) -> Result<(), Error> {
+    secret_key
+        .add_assign(&tweaking_factor[..])
+        .map_err(|_| Error::InvalidTweak)
+}
+
 /// Helpers for writing test functions working with commit-verify scheme
 #[cfg(test)]
 pub mod test_helpers {
@@ -265,7 +286,7 @@ pub mod test_helpers {
             sk[2] = (i >> 16) as u8;
 
             ret.push(secp256k1::PublicKey::from_secret_key(
-                &secp256k1::SECP256K1,
+                secp256k1::SECP256K1,
                 &secp256k1::SecretKey::from_slice(&sk[..]).unwrap(),
             ));
         }
@@ -369,11 +390,11 @@ mod test {
         let all_keys = gen_secp_pubkeys(6);
         let other_key = all_keys[0];
         for msg in &messages {
-            for mut pk in all_keys[1..].to_vec() {
-                let original = pk.clone();
+            for mut pk in all_keys[1..].iter().copied() {
+                let original = pk;
                 let mut keyset = bset![pk];
                 let mut keyset2 = bset![pk];
-                let mut pk2 = pk.clone();
+                let mut pk2 = pk;
                 let factor1 = commit(&mut keyset, &mut pk, &tag, &msg).unwrap();
                 let factor2 =
                     commit(&mut keyset2, &mut pk2, &tag2, &msg).unwrap();
@@ -407,7 +428,7 @@ mod test {
                 let tweaking_factor = *hmac.as_inner();
                 let mut altkey = original;
                 altkey
-                    .add_exp_assign(&secp256k1::SECP256K1, &tweaking_factor[..])
+                    .add_exp_assign(secp256k1::SECP256K1, &tweaking_factor[..])
                     .unwrap();
                 assert_eq!(altkey, pk);
 
@@ -430,38 +451,29 @@ mod test {
                     assert_ne!(pk, other_commitment);
 
                     // Make sure we can't cross-verify
-                    assert_eq!(
-                        verify(
-                            other_commitment,
-                            &bset![original],
-                            original,
-                            &tag,
-                            &msg
-                        ),
-                        false
-                    );
+                    assert!(!verify(
+                        other_commitment,
+                        &bset![original],
+                        original,
+                        &tag,
+                        &msg
+                    ));
                 }
 
                 // Verify commitment
                 assert!(verify(pk, &bset![original], original, &tag, &msg));
 
                 // Make sure we can't cross-verify with different tag
-                assert_eq!(
-                    verify(pk, &bset![original], original, &tag2, &msg),
-                    false
-                );
+                assert!(!verify(pk, &bset![original], original, &tag2, &msg));
 
                 // Make sure we can't cross-verify with different message
-                assert_eq!(
-                    verify(
-                        pk,
-                        &bset![original],
-                        original,
-                        &tag2,
-                        &b"some other message"
-                    ),
-                    false
-                );
+                assert!(!verify(
+                    pk,
+                    &bset![original],
+                    original,
+                    &tag2,
+                    &b"some other message"
+                ));
             }
         }
     }
@@ -474,13 +486,13 @@ mod test {
         let all_keys = gen_secp_pubkeys(6);
         let other_key = all_keys[0];
         let original_keyset: BTreeSet<_> =
-            all_keys[1..].to_vec().into_iter().collect();
+            all_keys[1..].iter().copied().collect();
         for msg in &messages {
             for mut pk in original_keyset.clone() {
-                let original = pk.clone();
+                let original = pk;
                 let mut keyset = original_keyset.clone();
                 let mut keyset2 = original_keyset.clone();
-                let mut pk2 = pk.clone();
+                let mut pk2 = pk;
                 let factor1 = commit(&mut keyset, &mut pk, &tag, &msg).unwrap();
                 let factor2 =
                     commit(&mut keyset2, &mut pk2, &tag2, &msg).unwrap();
@@ -518,7 +530,7 @@ mod test {
                 let tweaking_factor = *hmac.as_inner();
                 let mut altkey = original;
                 altkey
-                    .add_exp_assign(&secp256k1::SECP256K1, &tweaking_factor[..])
+                    .add_exp_assign(secp256k1::SECP256K1, &tweaking_factor[..])
                     .unwrap();
                 // It must not match because done with a single key, not
                 // their sum
@@ -543,54 +555,39 @@ mod test {
                     assert_ne!(pk, other_pk);
 
                     // Make sure we can't cross-verify
-                    assert_eq!(
-                        verify(
-                            other_pk,
-                            &bset![original],
-                            original,
-                            &tag,
-                            &msg
-                        ),
-                        false
-                    );
-                    assert_eq!(
-                        verify(
-                            other_pk,
-                            &original_keyset,
-                            original,
-                            &tag,
-                            &msg
-                        ),
-                        false
-                    );
+                    assert!(!verify(
+                        other_pk,
+                        &bset![original],
+                        original,
+                        &tag,
+                        &msg
+                    ));
+                    assert!(!verify(
+                        other_pk,
+                        &original_keyset,
+                        original,
+                        &tag,
+                        &msg
+                    ));
                 }
 
                 // Verify commitment
                 assert!(verify(pk, &original_keyset, original, &tag, &msg));
 
                 // Make sure we can't cross-verify with a single key in a set
-                assert_eq!(
-                    verify(pk, &bset![original], original, &tag, &msg),
-                    false
-                );
+                assert!(!verify(pk, &bset![original], original, &tag, &msg));
 
                 // Make sure we can't cross-verify with different tag
-                assert_eq!(
-                    verify(pk, &original_keyset, original, &tag2, &msg),
-                    false
-                );
+                assert!(!verify(pk, &original_keyset, original, &tag2, &msg));
 
                 // Make sure we can't cross-verify with different message
-                assert_eq!(
-                    verify(
-                        pk,
-                        &original_keyset,
-                        original,
-                        &tag2,
-                        &b"some other message"
-                    ),
-                    false
-                );
+                assert!(!verify(
+                    pk,
+                    &original_keyset,
+                    original,
+                    &tag2,
+                    &b"some other message"
+                ));
             }
         }
     }
@@ -601,8 +598,7 @@ mod test {
         let tag = sha256::Hash::hash(b"ProtoTag");
         let all_keys = gen_secp_pubkeys(6);
         let mut pk = all_keys[0];
-        let mut keyset: BTreeSet<_> =
-            all_keys[1..].to_vec().into_iter().collect();
+        let mut keyset: BTreeSet<_> = all_keys[1..].iter().copied().collect();
         let _ = commit(&mut keyset, &mut pk, &tag, b"Message").unwrap();
     }
 
@@ -621,4 +617,31 @@ mod test {
         let mut keyset = bset![pubkey, negkey];
         let _ = commit(&mut keyset, &mut pubkey, &tag, b"Message").unwrap();
     }
+
+    #[test]
+    fn test_tweak_secret_key() {
+        let tag = sha256::Hash::hash(b"ProtoTag");
+        let mut secret_key = secp256k1::SecretKey::from_slice(&[1u8; 32])
+            .expect("valid secret key");
+        let mut pubkey = secp256k1::PublicKey::from_secret_key(
+            secp256k1::SECP256K1,
+            &secret_key,
+        );
+        let mut keyset = bset![pubkey];
+        keyset.extend(gen_secp_pubkeys(3));
+
+        for msg in gen_messages() {
+            let original = pubkey;
+            let factor = commit(&mut keyset, &mut pubkey, &tag, &msg).unwrap();
+            tweak_secret_key(&mut secret_key, &factor).unwrap();
+            assert_ne!(pubkey, original);
+            assert_eq!(
+                secp256k1::PublicKey::from_secret_key(
+                    secp256k1::SECP256K1,
+                    &secret_key
+                ),
+                pubkey
+            );
+        }
+    }
 }
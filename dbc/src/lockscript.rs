@@ -38,6 +38,11 @@ use super::{Container, Error, KeysetCommitment, Proof, ScriptEncodeData};
 use crate::KeysetContainer;
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
 pub struct LockscriptContainer {
     pub script: LockScript,
     pub pubkey: secp256k1::PublicKey,
@@ -107,6 +112,11 @@ impl Container for LockscriptContainer {
 )]
 #[display(inner)]
 #[wrapper(LowerHex, UpperHex)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
 pub struct LockscriptCommitment(LockScript);
 
 impl<MSG> EmbedCommitVerify<MSG> for LockscriptCommitment
@@ -255,7 +265,7 @@ mod test {
 
             let pk = bitcoin::PublicKey {
                 key: secp256k1::PublicKey::from_secret_key(
-                    &secp256k1::SECP256K1,
+                    secp256k1::SECP256K1,
                     &secp256k1::SecretKey::from_slice(&sk[..])
                         .expect("secret key"),
                 ),
@@ -281,7 +291,7 @@ mod test {
     fn test_no_keys_and_hashes() {
         let tag = sha256::Hash::hash(b"TEST_TAG");
         let (keys, _, dummy_hashes) = gen_test_data();
-        let sha_hash = sha256::Hash::hash(&"(nearly)random string".as_bytes());
+        let sha_hash = sha256::Hash::hash("(nearly)random string".as_bytes());
 
         let ms = vec![
             ms_str!("older(921)"),
@@ -316,14 +326,14 @@ mod test {
         let tag = sha256::Hash::hash(b"TEST_TAG");
         let (keys, _, _) = gen_test_data();
 
-        let mut uncompressed = keys[5];
-        uncompressed.compressed = false;
+        let mut _uncompressed = keys[5];
+        _uncompressed.compressed = false;
         let ms = vec![
             ms_str!("c:pk_k({})", keys[1]),
             ms_str!("c:pk_k({})", keys[2]),
             ms_str!("c:pk_k({})", keys[3]),
             ms_str!("c:pk_k({})", keys[4]),
-            //ms_str!("c:pk({})", uncompressed),
+            //ms_str!("c:pk({})", _uncompressed),
         ];
 
         ms.into_iter()
@@ -381,14 +391,14 @@ mod test {
         let tag = sha256::Hash::hash(b"TEST_TAG");
         let (keys, _, _) = gen_test_data();
 
-        let mut uncompressed = keys[5];
-        uncompressed.compressed = false;
+        let mut _uncompressed = keys[5];
+        _uncompressed.compressed = false;
         let ms = vec![
             ms_str!("c:pk_k({})", keys[0]),
             ms_str!("c:pk_k({})", keys[1]),
             ms_str!("c:pk_k({})", keys[2]),
             ms_str!("c:pk_k({})", keys[3]),
-            //ms_str!("c:pk_k({})", uncompressed),
+            //ms_str!("c:pk_k({})", _uncompressed),
         ];
 
         ms.into_iter()
@@ -40,6 +40,11 @@ use crate::lnpbp1;
 /// must be hashed during commitment process. Here we use pre-hashed version
 /// of the tag in order to maximize performance for multiple commitments.
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
 pub struct PubkeyContainer {
     /// The original public key: host for commitment
     pub pubkey: secp256k1::PublicKey,
@@ -91,6 +96,11 @@ impl Container for PubkeyContainer {
 #[derive(Wrapper, Clone, PartialEq, Eq, Hash, Debug, Display, From)]
 #[display("{0}", alt = "{_0:#}*")]
 #[wrapper(FromStr, LowerHex)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
 pub struct PubkeyCommitment(secp256k1::PublicKey);
 
 impl<MSG> EmbedCommitVerify<MSG> for PubkeyCommitment
@@ -0,0 +1,173 @@
+// BP Core Library implementing LNP/BP specifications & standards related to
+// bitcoin protocol
+//
+// Written in 2020-2022 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the Apache 2.0 License
+// along with this software.
+// If not, see <https://opensource.org/licenses/Apache-2.0>.
+
+//! Serde support for types having both string and strict encoding
+//! representations.
+//!
+//! Human-readable formats (like JSON or YAML) use the string produced by the
+//! type [`Display`] implementation and parsed with its [`FromStr`]
+//! implementation; binary formats (like bincode) use strict encoding of the
+//! type serialized as a byte string. The [`serde_str_or_strict`] macro
+//! implements serde traits for a type in this way.
+
+use std::fmt::{self, Display, Formatter};
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+use serde::de::{Error, Visitor};
+use serde::{Deserializer, Serializer};
+#[doc(hidden)]
+pub use serde_crate as serde;
+use strict_encoding::{StrictDecode, StrictEncode};
+
+/// Serializes `value` as a string in human-readable formats and as
+/// strict-encoded bytes in binary formats
+pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Display + StrictEncode,
+    S: Serializer,
+{
+    if serializer.is_human_readable() {
+        serializer.serialize_str(&value.to_string())
+    } else {
+        let data = strict_encoding::strict_serialize(value)
+            .map_err(serde::ser::Error::custom)?;
+        serializer.serialize_bytes(&data)
+    }
+}
+
+/// Deserializes value serialized with [`serialize`]. The `expecting` string
+/// describes the string representation of the value in error messages.
+pub fn deserialize<'de, T, D>(
+    deserializer: D,
+    expecting: &'static str,
+) -> Result<T, D::Error>
+where
+    T: FromStr + StrictDecode,
+    T::Err: Display,
+    D: Deserializer<'de>,
+{
+    let visitor = StrOrStrictVisitor {
+        expecting,
+        phantom: PhantomData,
+    };
+    if deserializer.is_human_readable() {
+        deserializer.deserialize_str(visitor)
+    } else {
+        deserializer.deserialize_bytes(visitor)
+    }
+}
+
+struct StrOrStrictVisitor<T> {
+    expecting: &'static str,
+    phantom: PhantomData<T>,
+}
+
+impl<T> Visitor<'_> for StrOrStrictVisitor<T>
+where
+    T: FromStr + StrictDecode,
+    T::Err: Display,
+{
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "{} or strict-encoded bytes", self.expecting)
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        T::from_str(v).map_err(E::custom)
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        strict_encoding::strict_deserialize(v).map_err(E::custom)
+    }
+}
+
+/// Implements `serde::Serialize` and `serde::Deserialize` for a type using
+/// its string representation in human-readable formats and strict encoding
+/// in binary formats.
+///
+/// The first argument is the type, which must implement [`Display`],
+/// [`FromStr`], [`StrictEncode`] and [`StrictDecode`]; the second one is a
+/// string literal describing the string representation in error messages.
+#[macro_export]
+macro_rules! serde_str_or_strict {
+    ($ty:ty, $expecting:literal) => {
+        impl $crate::serde_utils::serde::Serialize for $ty {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: $crate::serde_utils::serde::Serializer,
+            {
+                $crate::serde_utils::serialize(self, serializer)
+            }
+        }
+
+        impl<'de> $crate::serde_utils::serde::Deserialize<'de> for $ty {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: $crate::serde_utils::serde::Deserializer<'de>,
+            {
+                $crate::serde_utils::deserialize(deserializer, $expecting)
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    #[derive(Wrapper, Copy, Clone, PartialEq, Eq, Debug, From)]
+    #[wrapper(Display, FromStr)]
+    struct Height(u16);
+
+    impl strict_encoding::Strategy for Height {
+        type Strategy = strict_encoding::strategies::Wrapped;
+    }
+
+    serde_str_or_strict!(Height, "decimal block height");
+
+    #[test]
+    fn test_serde_str_or_strict() {
+        let height = Height(0x0201);
+
+        let json = serde_json::to_string(&height).unwrap();
+        assert_eq!(json, "\"513\"");
+        assert_eq!(serde_json::from_str::<Height>(&json).unwrap(), height);
+        assert_eq!(
+            serde_json::from_str::<Height>("\"-1\"")
+                .unwrap_err()
+                .to_string(),
+            "invalid digit found in string at line 1 column 4"
+        );
+        assert_eq!(
+            serde_json::from_str::<Height>("513")
+                .unwrap_err()
+                .to_string(),
+            "invalid type: integer `513`, expected decimal block height or \
+             strict-encoded bytes at line 1 column 3"
+        );
+
+        // bincode prefixes byte strings with their 64-bit length
+        let data = bincode::serialize(&height).unwrap();
+        assert_eq!(data, [2, 0, 0, 0, 0, 0, 0, 0, 0x01, 0x02]);
+        assert_eq!(bincode::deserialize::<Height>(&data).unwrap(), height);
+        assert!(bincode::deserialize::<Height>(&data[..9]).is_err());
+    }
+}
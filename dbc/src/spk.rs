@@ -14,8 +14,11 @@
 // If not, see <https://opensource.org/licenses/Apache-2.0>.
 
 use core::convert::TryFrom;
+use std::fmt::{self, Formatter, LowerHex};
 
+use amplify::hex::ToHex;
 use amplify::Wrapper;
+use bitcoin::blockdata::opcodes::all::{OP_PUSHBYTES_32, OP_PUSHNUM_1};
 use bitcoin::blockdata::script::Script;
 use bitcoin::hashes::{sha256, Hmac};
 use bitcoin::secp256k1;
@@ -26,6 +29,7 @@ use super::{
     Container, Error, LockscriptCommitment, LockscriptContainer, Proof,
     PubkeyCommitment, PubkeyContainer, TaprootCommitment, TaprootContainer,
 };
+use crate::taproot;
 
 /// Enum defining how given `scriptPubkey` is constructed from the script data
 /// or a public key. It is similar to Bitcoin Core descriptors, however it does
@@ -38,6 +42,11 @@ use super::{
 /// proof (it can be guessed from a given proof and `scriptPubkey` and we'd like
 /// to preserve space with client-validated data).
 #[derive(Clone, PartialEq, Eq, Hash, Debug, Display)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
 #[non_exhaustive]
 pub enum ScriptEncodeMethod {
     #[display("PublicKey")]
@@ -65,19 +74,20 @@ pub enum ScriptEncodeMethod {
 /// Structure keeping the minimum of information (bytewise) required to verify
 /// deterministic bitcoin commitment given only the transaction source, its
 /// fee and protocol-specific constants. It is a part of the [`Proof`] data.
-#[derive(Clone, PartialEq, Eq, Hash, Debug, Display)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Display, Default)]
 #[derive(StrictEncode, StrictDecode)]
 #[cfg_attr(
     feature = "serde",
     derive(Serialize, Deserialize),
     serde(crate = "serde_crate")
 )]
-#[display(doc_comments)]
 pub enum ScriptEncodeData {
     /// Public key. Since we keep the original public key as a part of a proof,
     /// and value of the tweaked key can be reconstructed with DBC source data
     /// and the original pubkey, so we do not need to keep any additional data
     /// here).
+    #[default]
+    #[display("pubkey")]
     SinglePubkey,
 
     /// Any output containing script information, aside from OP_RETURN outputs
@@ -86,21 +96,51 @@ pub enum ScriptEncodeData {
     /// the deteministic bitcoin commitment is verified, the output may be
     /// still unspent and we will not be able to reconstruct the script without
     /// this data kept in the client-validated part.
+    #[display("script({0})")]
     LockScript(LockScript),
 
     // TODO: Add `WrappedWitnessScript(WitnessScript) variant
     /// Taproot-based outputs. We need to keep only the hash of the taprscript
     /// merkle tree root.
+    #[display("taproot({0})")]
     Taproot(sha256::Hash),
 }
 
-impl Default for ScriptEncodeData {
-    fn default() -> Self {
-        Self::SinglePubkey
+// Hex representation of the source data is its strict encoding; scripts
+// exceeding the strict encoding size limit can't be represented
+impl LowerHex for ScriptEncodeData {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let data =
+            strict_encoding::strict_serialize(self).map_err(|_| fmt::Error)?;
+        f.write_str(&data.to_hex())
+    }
+}
+
+#[cfg(feature = "test-utils")]
+impl<'a> arbitrary::Arbitrary<'a> for ScriptEncodeData {
+    fn arbitrary(
+        u: &mut arbitrary::Unstructured<'a>,
+    ) -> arbitrary::Result<Self> {
+        use bitcoin::hashes::Hash;
+
+        Ok(match u.int_in_range(0..=2)? {
+            0 => ScriptEncodeData::SinglePubkey,
+            1 => ScriptEncodeData::LockScript(LockScript::from_inner(
+                Script::from(u.arbitrary::<Vec<u8>>()?),
+            )),
+            _ => ScriptEncodeData::Taproot(sha256::Hash::from_inner(
+                u.arbitrary::<[u8; 32]>()?,
+            )),
+        })
     }
 }
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
 pub struct SpkContainer {
     pub pubkey: secp256k1::PublicKey,
     pub method: ScriptEncodeMethod,
@@ -146,46 +186,54 @@ impl Container for SpkContainer {
         };
 
         let mut proof = proof.clone();
-        let method = match descriptors::Compact::try_from(host.clone())? {
-            descriptors::Compact::Sh(script_hash) => {
-                let script = Script::new_p2sh(&script_hash);
-                if let Some(lockscript) = lockscript {
-                    if *lockscript.to_pubkey_script(Category::Hashed) == script
-                    {
-                        ScriptEncodeMethod::ScriptHash
-                    } else if *lockscript.to_pubkey_script(Category::Nested)
+        let method = if is_p2tr(host) {
+            // Witness v1 outputs are not yet supported by `descriptors`
+            ScriptEncodeMethod::Taproot
+        } else {
+            match descriptors::Compact::try_from(host.clone())? {
+                descriptors::Compact::Sh(script_hash) => {
+                    let script = Script::new_p2sh(&script_hash);
+                    if let Some(lockscript) = lockscript {
+                        if *lockscript.to_pubkey_script(Category::Hashed)
+                            == script
+                        {
+                            ScriptEncodeMethod::ScriptHash
+                        } else if *lockscript.to_pubkey_script(Category::Nested)
+                            == script
+                        {
+                            // TODO: Fail here, use WrappedWitnessScript variant
+                            ScriptEncodeMethod::ShWScriptHash
+                        } else {
+                            return Err(Error::InvalidProofStructure);
+                        }
+                    } else if *proof.pubkey.to_pubkey_script(Category::Nested)
                         == script
                     {
-                        // TODO: Fail here, use WrappedWitnessScript variant
-                        ScriptEncodeMethod::ShWScriptHash
+                        ScriptEncodeMethod::ShWPubkeyHash
                     } else {
                         return Err(Error::InvalidProofStructure);
                     }
-                } else if *proof.pubkey.to_pubkey_script(Category::Nested)
-                    == script
+                }
+                descriptors::Compact::Bare(script)
+                    if script.as_inner().is_op_return() =>
                 {
-                    ScriptEncodeMethod::ShWPubkeyHash
-                } else {
-                    return Err(Error::InvalidProofStructure);
+                    ScriptEncodeMethod::OpReturn
                 }
+                descriptors::Compact::Bare(script) => {
+                    proof.source = ScriptEncodeData::LockScript(
+                        LockScript::from(script.to_inner()),
+                    );
+                    ScriptEncodeMethod::Bare
+                }
+                descriptors::Compact::Pk(_) => ScriptEncodeMethod::PublicKey,
+                descriptors::Compact::Pkh(_) => ScriptEncodeMethod::PubkeyHash,
+                descriptors::Compact::Wpkh(_) => {
+                    ScriptEncodeMethod::WPubkeyHash
+                }
+                descriptors::Compact::Wsh(_) => ScriptEncodeMethod::WScriptHash,
+                descriptors::Compact::Taproot(_) => ScriptEncodeMethod::Taproot,
+                _ => unimplemented!(), // TODO: Fail with error here
             }
-            descriptors::Compact::Bare(script)
-                if script.as_inner().is_op_return() =>
-            {
-                ScriptEncodeMethod::OpReturn
-            }
-            descriptors::Compact::Bare(script) => {
-                proof.source = ScriptEncodeData::LockScript(LockScript::from(
-                    script.to_inner(),
-                ));
-                ScriptEncodeMethod::Bare
-            }
-            descriptors::Compact::Pk(_) => ScriptEncodeMethod::PublicKey,
-            descriptors::Compact::Pkh(_) => ScriptEncodeMethod::PubkeyHash,
-            descriptors::Compact::Wpkh(_) => ScriptEncodeMethod::WPubkeyHash,
-            descriptors::Compact::Wsh(_) => ScriptEncodeMethod::WScriptHash,
-            descriptors::Compact::Taproot(_) => ScriptEncodeMethod::Taproot,
-            _ => unimplemented!(), // TODO: Fail with error here
         };
         let proof = proof;
 
@@ -249,6 +297,14 @@ impl Container for SpkContainer {
     }
 }
 
+/// Detects witness v1 `scriptPubkey` with a 32-byte witness program
+fn is_p2tr(script_pubkey: &PubkeyScript) -> bool {
+    let script = script_pubkey.as_inner();
+    script.len() == 34
+        && script[0] == OP_PUSHNUM_1.into_u8()
+        && script[1] == OP_PUSHBYTES_32.into_u8()
+}
+
 /// [`PubkeyScript`] containing LNPBP-2 commitment
 #[derive(
     Wrapper, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug,
@@ -256,6 +312,11 @@ impl Container for SpkContainer {
 )]
 #[display(inner)]
 #[wrapper(LowerHex, UpperHex)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
 pub struct SpkCommitment(PubkeyScript);
 
 impl<MSG> EmbedCommitVerify<MSG> for SpkCommitment
@@ -307,15 +368,12 @@ where
                     tag: container.tag,
                     tweaking_factor: None,
                 };
-                let _taproot = TaprootCommitment::embed_commit(
+                let taproot = TaprootCommitment::embed_commit(
                     &mut taproot_container,
                     msg,
                 )?;
                 container.tweaking_factor = taproot_container.tweaking_factor;
-                // TODO #2: Finalize taproot commitments once taproot will be
-                //          finalized. We don't know yet how to form scripPubkey
-                //          from Taproot data
-                unimplemented!()
+                taproot.to_pubkey_script()?
             }
             ScriptEncodeData::SinglePubkey => {
                 let mut pubkey_container = PubkeyContainer {
@@ -333,6 +391,7 @@ where
                     PubkeyHash => pubkey.to_pubkey_script(Category::Hashed),
                     WPubkeyHash => pubkey.to_pubkey_script(Category::SegWit),
                     ShWScriptHash => pubkey.to_pubkey_script(Category::Nested),
+                    Taproot => taproot::key_path_pubkey_script(&pubkey)?,
                     OpReturn => {
                         let ser = pubkey.serialize();
                         if ser[0] != 0x02 {
@@ -347,3 +406,107 @@ where
         Ok(SpkCommitment::from_inner(script_pubkey))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use bitcoin::hashes::Hash;
+
+    use super::*;
+    use crate::lnpbp1::test_helpers::*;
+
+    #[test]
+    fn test_taproot_reconstruct_verify() {
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let script_root = sha256::Hash::hash(b"script root");
+        let msg = "test message";
+        let mut container = SpkContainer::construct(
+            &tag,
+            gen_secp_pubkeys(1)[0],
+            ScriptEncodeData::Taproot(script_root),
+            ScriptEncodeMethod::Taproot,
+        );
+        let commitment =
+            SpkCommitment::embed_commit(&mut container, &msg).unwrap();
+        assert!(commitment.as_inner().is_witness_program());
+
+        let reconstructed = SpkContainer::reconstruct(
+            &container.to_proof(),
+            &tag,
+            commitment.as_inner(),
+        )
+        .unwrap();
+        assert_eq!(reconstructed.method, ScriptEncodeMethod::Taproot);
+        assert!(commitment.verify(&reconstructed, &msg).unwrap());
+        assert!(!commitment.verify(&reconstructed, &"other message").unwrap());
+    }
+
+    #[test]
+    fn test_taproot_key_path_reconstruct_verify() {
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let msg = "test message";
+        let mut container = SpkContainer::construct(
+            &tag,
+            gen_secp_pubkeys(1)[0],
+            ScriptEncodeData::SinglePubkey,
+            ScriptEncodeMethod::Taproot,
+        );
+        let commitment =
+            SpkCommitment::embed_commit(&mut container, &msg).unwrap();
+        assert!(commitment.as_inner().is_witness_program());
+
+        let proof = container.to_proof();
+        let reconstructed =
+            SpkContainer::reconstruct(&proof, &tag, commitment.as_inner())
+                .unwrap();
+        assert!(commitment.verify(&reconstructed, &msg).unwrap());
+
+        // Proof claiming presence of a script tree must not verify against
+        // a key-path-only output
+        let proof = Proof {
+            pubkey: proof.pubkey,
+            source: ScriptEncodeData::Taproot(sha256::Hash::hash(b"root")),
+        };
+        let reconstructed =
+            SpkContainer::reconstruct(&proof, &tag, commitment.as_inner())
+                .unwrap();
+        assert!(!commitment.verify(&reconstructed, &msg).unwrap());
+    }
+
+    #[test]
+    fn test_encode_data_display() {
+        let pubkey = gen_secp_pubkeys(1)[0];
+        let script_root = sha256::Hash::hash(b"script root");
+        let data = ScriptEncodeData::Taproot(script_root);
+        assert_eq!(ScriptEncodeData::SinglePubkey.to_string(), "pubkey");
+        assert_eq!(data.to_string(), format!("taproot({})", script_root));
+        assert_eq!(
+            Proof {
+                pubkey,
+                source: data
+            }
+            .to_string(),
+            format!("proof({}, taproot({}))", pubkey, script_root)
+        );
+    }
+
+    #[test]
+    fn test_encode_data_lower_hex() {
+        let pubkey = gen_secp_pubkeys(1)[0];
+        let script_root = sha256::Hash::hash(b"script root");
+        let data = ScriptEncodeData::Taproot(script_root);
+        assert_eq!(format!("{:x}", ScriptEncodeData::SinglePubkey), "00");
+        assert_eq!(format!("{:x}", data), format!("02{}", script_root));
+        let proof = Proof {
+            pubkey,
+            source: data,
+        };
+        assert_eq!(
+            format!("{:x}", proof),
+            format!("{}02{}", pubkey, script_root)
+        );
+        assert_eq!(
+            format!("{:x}", proof),
+            strict_encoding::strict_serialize(&proof).unwrap().to_hex()
+        );
+    }
+}
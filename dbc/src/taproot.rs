@@ -13,16 +13,24 @@
 // along with this software.
 // If not, see <https://opensource.org/licenses/Apache-2.0>.
 
-use bitcoin::hashes::{sha256, Hmac};
-use bitcoin::secp256k1;
+use bitcoin::blockdata::script::Script;
+use bitcoin::hashes::{sha256, Hash, HashEngine, Hmac};
+use bitcoin::secp256k1::{self, schnorrsig};
+use bitcoin::util::taproot::TapTweakHash;
+use bitcoin_scripts::PubkeyScript;
 use commit_verify::EmbedCommitVerify;
 
 use super::{
-    Container, Error, Proof, PubkeyCommitment, PubkeyContainer,
+    lnpbp1, Container, Error, Proof, PubkeyCommitment, PubkeyContainer,
     ScriptEncodeData,
 };
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
 pub struct TaprootContainer {
     pub script_root: sha256::Hash,
     pub intermediate_key: secp256k1::PublicKey,
@@ -82,11 +90,118 @@ impl Container for TaprootContainer {
 }
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
 pub struct TaprootCommitment {
     pub script_root: sha256::Hash,
     pub intermediate_key_commitment: PubkeyCommitment,
 }
 
+impl TaprootCommitment {
+    /// Computes BIP-341 output key by tweaking the intermediate key, which
+    /// already contains LNPBP-1 commitment, with the tapscript merkle root.
+    /// Returns x-only output key together with its parity, which is required
+    /// for spending the output via script path.
+    pub fn output_key(&self) -> Result<(schnorrsig::PublicKey, bool), Error> {
+        tweak_output_key(
+            internal_key(&self.intermediate_key_commitment),
+            Some(&self.script_root),
+        )
+    }
+
+    /// Constructs witness version 1 `scriptPubkey` for the output key
+    /// produced by [`TaprootCommitment::output_key`]
+    pub fn to_pubkey_script(&self) -> Result<PubkeyScript, Error> {
+        let (output_key, _) = self.output_key()?;
+        Ok(p2tr_pubkey_script(output_key))
+    }
+}
+
+/// Constructs witness version 1 `scriptPubkey` for an output without script
+/// tree, where the intermediate key already contains LNPBP-1 commitment and
+/// can be spent only via key path.
+pub fn key_path_pubkey_script(
+    intermediate_key: &secp256k1::PublicKey,
+) -> Result<PubkeyScript, Error> {
+    let (output_key, _) =
+        tweak_output_key(internal_key(intermediate_key), None)?;
+    Ok(p2tr_pubkey_script(output_key))
+}
+
+/// Tweaks x-only internal key according to BIP-341 with a given tapscript
+/// merkle root, returning the output key and its parity. If there is no
+/// script tree, the key is tweaked with the hash of the internal key only.
+pub fn tweak_output_key(
+    internal_key: schnorrsig::PublicKey,
+    script_root: Option<&sha256::Hash>,
+) -> Result<(schnorrsig::PublicKey, bool), Error> {
+    let tweak = tap_tweak(&internal_key, script_root);
+    let mut output_key = internal_key;
+    let parity = output_key
+        .tweak_add_assign(secp256k1::SECP256K1, &tweak[..])
+        .map_err(|_| Error::TaprootTweak)?;
+    Ok((output_key, parity))
+}
+
+/// Produces key pair for spending via key path a taproot output, which
+/// intermediate key contains LNPBP-1 commitment with the `tweaking_factor`
+/// and which has a script tree with a given `script_root` (or no script tree,
+/// like outputs produced with [`key_path_pubkey_script`]).
+///
+/// The `secret_key` must correspond to the original intermediate key. The
+/// function applies LNPBP-1 tweaking factor, negates the key if its public
+/// key has odd Y coordinate, as required for BIP-340 x-only keys, and applies
+/// BIP-341 tweak, so the x-only public key of the returned key pair matches
+/// the output key.
+pub fn tweak_key_pair(
+    secret_key: &secp256k1::SecretKey,
+    tweaking_factor: &Hmac<sha256::Hash>,
+    script_root: Option<&sha256::Hash>,
+) -> Result<schnorrsig::KeyPair, Error> {
+    let mut secret_key = *secret_key;
+    lnpbp1::tweak_secret_key(&mut secret_key, tweaking_factor)?;
+    let mut key_pair =
+        schnorrsig::KeyPair::from_secret_key(secp256k1::SECP256K1, secret_key);
+    let internal_key =
+        schnorrsig::PublicKey::from_keypair(secp256k1::SECP256K1, &key_pair);
+    key_pair
+        .tweak_add_assign(
+            secp256k1::SECP256K1,
+            &tap_tweak(&internal_key, script_root)[..],
+        )
+        .map_err(|_| Error::TaprootTweak)?;
+    Ok(key_pair)
+}
+
+fn tap_tweak(
+    internal_key: &schnorrsig::PublicKey,
+    script_root: Option<&sha256::Hash>,
+) -> TapTweakHash {
+    let mut engine = TapTweakHash::engine();
+    engine.input(&internal_key.serialize());
+    if let Some(script_root) = script_root {
+        engine.input(&script_root[..]);
+    }
+    TapTweakHash::from_engine(engine)
+}
+
+fn internal_key(pubkey: &secp256k1::PublicKey) -> schnorrsig::PublicKey {
+    schnorrsig::PublicKey::from_slice(&pubkey.serialize()[1..])
+        .expect("x-only key from a valid public key can't fail")
+}
+
+fn p2tr_pubkey_script(output_key: schnorrsig::PublicKey) -> PubkeyScript {
+    Script::new_witness_program(
+        bitcoin::bech32::u5::try_from_u8(1)
+            .expect("witness version 1 is a valid u5 value"),
+        &output_key.serialize(),
+    )
+    .into()
+}
+
 impl<MSG> EmbedCommitVerify<MSG> for TaprootCommitment
 where
     MSG: AsRef<[u8]>,
@@ -114,3 +229,161 @@ where
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use amplify::hex::ToHex;
+    use bitcoin::hashes::hex::FromHex;
+
+    use super::*;
+    use crate::lnpbp1::test_helpers::*;
+
+    #[test]
+    fn test_taproot_commitment() {
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let script_root = sha256::Hash::hash(b"script root");
+        gen_secp_pubkeys(9)
+            .into_iter()
+            .for_each(|intermediate_key| {
+                embed_commit_verify_suite::<Vec<u8>, TaprootCommitment>(
+                    gen_messages(),
+                    &mut TaprootContainer {
+                        script_root,
+                        intermediate_key,
+                        tag,
+                        tweaking_factor: None,
+                    },
+                );
+            });
+    }
+
+    #[test]
+    fn test_bip341_output_key() {
+        // Test vector from BIP-341 `scriptPubKey` section (index 1)
+        let internal_key = schnorrsig::PublicKey::from_str(
+            "187791b6f712a8ea41c8ecdd0ee77fab3e85263b37e1ec18a3651926b3a6cf27",
+        )
+        .unwrap();
+        let script_root = sha256::Hash::from_hex(
+            "5b75adecf53548f3ec6ad7d78383bf84cc57b55a3127c72b9a2481752dd88b21",
+        )
+        .unwrap();
+        let (output_key, _) =
+            tweak_output_key(internal_key, Some(&script_root)).unwrap();
+        assert_eq!(
+            output_key.to_hex(),
+            "147c9c57132f6e7ecddba9800bb0c4449251c92a1e60371ee77557b6620f3ea3"
+        );
+    }
+
+    #[test]
+    fn test_bip341_key_path_output_key() {
+        // Test vector from BIP-341 `scriptPubKey` section (index 0)
+        let internal_key = schnorrsig::PublicKey::from_str(
+            "d6889cb081036e0faefa3a35157ad71086b123b2b144b649798b494c300a961d",
+        )
+        .unwrap();
+        let (output_key, _) = tweak_output_key(internal_key, None).unwrap();
+        assert_eq!(
+            output_key.to_hex(),
+            "53a1f6e454df1aa2776a2814a721372d6258050de330b3c6d10ee8f4e0dda343"
+        );
+    }
+
+    #[test]
+    fn test_taproot_pubkey_script() {
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let script_root = sha256::Hash::hash(b"script root");
+        let intermediate_key = gen_secp_pubkeys(1)[0];
+        let commitment = TaprootCommitment::embed_commit(
+            &mut TaprootContainer {
+                script_root,
+                intermediate_key,
+                tag,
+                tweaking_factor: None,
+            },
+            &"test message",
+        )
+        .unwrap();
+
+        let (output_key, parity) = commitment.output_key().unwrap();
+        let internal_key =
+            internal_key(&commitment.intermediate_key_commitment);
+        let mut engine = TapTweakHash::engine();
+        engine.input(&internal_key.serialize());
+        engine.input(&script_root[..]);
+        let tweak = TapTweakHash::from_engine(engine);
+        assert!(internal_key.tweak_add_check(
+            secp256k1::SECP256K1,
+            &output_key,
+            parity,
+            tweak.into_inner()
+        ));
+
+        let script_pubkey = commitment.to_pubkey_script().unwrap();
+        assert!(script_pubkey.is_witness_program());
+        assert_eq!(script_pubkey[0], 0x51);
+        assert_eq!(&script_pubkey[2..], &output_key.serialize()[..]);
+    }
+
+    #[test]
+    fn test_tweak_key_pair() {
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let script_root = sha256::Hash::hash(b"script root");
+        // Secret keys producing committed intermediate keys with both even
+        // and odd Y
+        let mut prefixes = vec![];
+        for byte in 1u8..=8 {
+            let secret_key =
+                secp256k1::SecretKey::from_slice(&[byte; 32]).unwrap();
+            let intermediate_key = secp256k1::PublicKey::from_secret_key(
+                secp256k1::SECP256K1,
+                &secret_key,
+            );
+
+            let mut container = TaprootContainer {
+                script_root,
+                intermediate_key,
+                tag,
+                tweaking_factor: None,
+            };
+            let commitment =
+                TaprootCommitment::embed_commit(&mut container, &"message")
+                    .unwrap();
+            let tweaking_factor = container.tweaking_factor.unwrap();
+            prefixes
+                .push(commitment.intermediate_key_commitment.serialize()[0]);
+
+            let key_pair = tweak_key_pair(
+                &secret_key,
+                &tweaking_factor,
+                Some(&script_root),
+            )
+            .unwrap();
+            assert_eq!(
+                schnorrsig::PublicKey::from_keypair(
+                    secp256k1::SECP256K1,
+                    &key_pair
+                ),
+                commitment.output_key().unwrap().0
+            );
+
+            let key_pair =
+                tweak_key_pair(&secret_key, &tweaking_factor, None).unwrap();
+            let script_pubkey =
+                key_path_pubkey_script(&commitment.intermediate_key_commitment)
+                    .unwrap();
+            assert_eq!(
+                &script_pubkey[2..],
+                &schnorrsig::PublicKey::from_keypair(
+                    secp256k1::SECP256K1,
+                    &key_pair
+                )
+                .serialize()[..]
+            );
+        }
+        assert!(prefixes.contains(&0x02) && prefixes.contains(&0x03));
+    }
+}
@@ -0,0 +1,43 @@
+// BP Core Library implementing LNP/BP specifications & standards related to
+// bitcoin protocol
+//
+// Written in 2020-2022 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the Apache 2.0 License
+// along with this software.
+// If not, see <https://opensource.org/licenses/Apache-2.0>.
+
+//! Property-based testing helpers for the types implementing
+//! [`arbitrary::Arbitrary`], like [`crate::Anchor`] and [`crate::Proof`], or
+//! the seal types from `bp-seals`.
+
+use std::fmt::Debug;
+
+use arbitrary::{Arbitrary, Unstructured};
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+/// Maximal size of the random data the values are generated from
+pub const MAX_DATA_LEN: usize = 4096;
+
+/// Proptest strategy generating values of type `T` out of its
+/// [`Arbitrary`] implementation.
+///
+/// The values are built from a random byte buffer, so on failure proptest
+/// shrinks the buffer and the value with it. Buffers from which `T` can't be
+/// constructed are rejected.
+pub fn strategy<T>() -> impl Strategy<Value = T>
+where
+    T: for<'a> Arbitrary<'a> + Debug,
+{
+    vec(any::<u8>(), 0..MAX_DATA_LEN)
+        .prop_filter_map("data not representing a value", |data| {
+            T::arbitrary_take_rest(Unstructured::new(&data)).ok()
+        })
+}
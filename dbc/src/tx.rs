@@ -24,6 +24,11 @@ use super::{
 };
 
 #[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
 pub struct TxContainer {
     pub protocol_factor: u32,
     pub fee: u64,
@@ -34,6 +39,11 @@ pub struct TxContainer {
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
 pub struct TxSupplement {
     pub protocol_factor: u32,
     pub fee: u64,
@@ -68,6 +78,16 @@ impl TxContainer {
         me
     }
 
+    /// Checks that the transaction has a number of outputs which can be
+    /// used for the commitment output selection by [`TxContainer::vout`]
+    pub fn check_outputs(tx: &Transaction) -> Result<(), Error> {
+        let nouts = tx.output.len();
+        if nouts == 0 || nouts > u16::MAX as usize {
+            return Err(Error::InvalidOutputCount(nouts));
+        }
+        Ok(())
+    }
+
     pub fn vout(&self) -> usize {
         let nouts = self.tx.output.len() as u16;
         let vout = ((self.fee + (self.protocol_factor as u64)) % (nouts as u64))
@@ -85,6 +105,7 @@ impl Container for TxContainer {
         supplement: &Self::Supplement,
         host: &Self::Host,
     ) -> Result<Self, Error> {
+        Self::check_outputs(host)?;
         let mut me = Self {
             protocol_factor: supplement.protocol_factor,
             fee: supplement.fee,
@@ -126,6 +147,11 @@ impl Container for TxContainer {
 
 /// [`bitcoin::Transaction`] containing LNPBP-3 commitment
 #[derive(Wrapper, Clone, PartialEq, Eq, Hash, Debug, From)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
 pub struct TxCommitment(Transaction);
 
 impl<MSG> EmbedCommitVerify<MSG> for TxCommitment
@@ -208,6 +234,6 @@ mod test {
 
         let commitment =
             TxCommitment::embed_commit(&mut container, &msg).unwrap();
-        assert_eq!(commitment.verify(&container, &msg).unwrap(), true);
+        assert!(commitment.verify(&container, &msg).unwrap());
     }
 }
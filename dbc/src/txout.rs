@@ -25,6 +25,11 @@ use super::{
 };
 
 #[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
 pub struct TxoutContainer {
     pub value: u64,
     pub script_container: SpkContainer,
@@ -90,6 +95,11 @@ impl Container for TxoutContainer {
 
 /// [`bitcoin::TxOut`] containing LNPBP-2 commitment
 #[derive(Wrapper, Clone, PartialEq, Eq, Hash, Default, Debug, From)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
 pub struct TxoutCommitment(TxOut);
 
 impl<MSG> EmbedCommitVerify<MSG> for TxoutCommitment
@@ -13,6 +13,9 @@
 // along with this software.
 // If not, see <https://opensource.org/licenses/Apache-2.0>.
 
+use std::fmt::{self, Formatter, LowerHex};
+
+use amplify::hex::ToHex;
 use amplify::DumbDefault;
 use bitcoin::secp256k1;
 
@@ -41,12 +44,40 @@ pub trait Container: Sized {
     derive(Serialize, Deserialize),
     serde(crate = "serde_crate")
 )]
-#[display("proof({pubkey}, {source}")]
+#[display("proof({pubkey}, {source})")]
 pub struct Proof {
     pub pubkey: secp256k1::PublicKey,
     pub source: ScriptEncodeData,
 }
 
+// Hex representation of the proof is its strict encoding: the serialized
+// original public key followed by the hex representation of the source data
+impl LowerHex for Proof {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{:x}", self.pubkey.serialize().to_hex(), self.source)
+    }
+}
+
+#[cfg(feature = "test-utils")]
+impl<'a> arbitrary::Arbitrary<'a> for Proof {
+    fn arbitrary(
+        u: &mut arbitrary::Unstructured<'a>,
+    ) -> arbitrary::Result<Self> {
+        // Zero and out-of-order scalars (including the all-zero bytes produced
+        // by exhausted input) fall back to the unit key
+        let secret_key =
+            secp256k1::SecretKey::from_slice(&u.arbitrary::<[u8; 32]>()?)
+                .unwrap_or(secp256k1::key::ONE_KEY);
+        Ok(Proof {
+            pubkey: secp256k1::PublicKey::from_secret_key(
+                secp256k1::SECP256K1,
+                &secret_key,
+            ),
+            source: u.arbitrary()?,
+        })
+    }
+}
+
 impl DumbDefault for Proof {
     fn dumb_default() -> Self {
         Proof {
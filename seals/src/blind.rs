@@ -21,6 +21,8 @@ use bitcoin::{OutPoint, Txid};
 use commit_verify::{commit_encode, CommitConceal, CommitVerify, TaggedHash};
 use lnpbp_bech32::{FromBech32Str, ToBech32String};
 
+use crate::ChainParseError;
+
 /// Data required to generate or reveal the information about blinded
 /// transaction outpoint
 #[derive(
@@ -48,7 +50,7 @@ pub struct OutpointReveal {
 impl From<OutpointReveal> for OutPoint {
     #[inline]
     fn from(reveal: OutpointReveal) -> Self {
-        OutPoint::new(reveal.txid, reveal.vout as u32)
+        OutPoint::new(reveal.txid, reveal.vout)
     }
 }
 
@@ -57,7 +59,7 @@ impl From<OutPoint> for OutpointReveal {
         Self {
             blinding: thread_rng().next_u64(),
             txid: outpoint.txid,
-            vout: outpoint.vout as u32,
+            vout: outpoint.vout,
         }
     }
 }
@@ -117,6 +119,10 @@ pub enum ParseError {
     /// wrong Bech32 representation of the blinded UTXO seal – {0}
     #[from]
     Bech32(lnpbp_bech32::Error),
+
+    /// wrong chain in the seal specification – {0}
+    #[from]
+    WrongChain(ChainParseError),
 }
 
 impl FromStr for OutpointReveal {
@@ -0,0 +1,418 @@
+// BP Core Library implementing LNP/BP specifications & standards related to
+// bitcoin protocol
+//
+// Written in 2020-2022 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the Apache 2.0 License
+// along with this software.
+// If not, see <https://opensource.org/licenses/Apache-2.0>.
+
+use std::convert::TryFrom;
+use std::io;
+use std::str::FromStr;
+
+use bitcoin::hashes::hex::FromHex;
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::{BlockHash, Network, Script};
+use strict_encoding::{StrictDecode, StrictEncode};
+
+/// Genesis block hash of bitcoin testnet version 4 (BIP-94)
+pub const TESTNET4_GENESIS_HASH: &str =
+    "00000000da84f2bafbbc53dee25a72ae507ff4914b867c565be350b0da8bf043";
+
+/// Challenge script of the default bitcoin signet
+pub const SIGNET_DEFAULT_CHALLENGE: &str = "512103ad5e0edad18cb1f0fc0d28a3d4f1f3e445640337489abb10404f2d1e086be430210359ef5021964fe22d6f8e05b2463c9540ce96883fe3b278760f048f5189f2e6c452ae";
+
+// Genesis block hashes and the default signet challenge hash in their
+// internal byte order, so they can be matched without constructing genesis
+// blocks or decoding hex strings
+const GENESIS_HASH_MAINNET: [u8; 32] = [
+    0x6F, 0xE2, 0x8C, 0x0A, 0xB6, 0xF1, 0xB3, 0x72, 0xC1, 0xA6, 0xA2, 0x46,
+    0xAE, 0x63, 0xF7, 0x4F, 0x93, 0x1E, 0x83, 0x65, 0xE1, 0x5A, 0x08, 0x9C,
+    0x68, 0xD6, 0x19, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+const GENESIS_HASH_TESTNET3: [u8; 32] = [
+    0x43, 0x49, 0x7F, 0xD7, 0xF8, 0x26, 0x95, 0x71, 0x08, 0xF4, 0xA3, 0x0F,
+    0xD9, 0xCE, 0xC3, 0xAE, 0xBA, 0x79, 0x97, 0x20, 0x84, 0xE9, 0x0E, 0xAD,
+    0x01, 0xEA, 0x33, 0x09, 0x00, 0x00, 0x00, 0x00,
+];
+const GENESIS_HASH_TESTNET4: [u8; 32] = [
+    0x43, 0xF0, 0x8B, 0xDA, 0xB0, 0x50, 0xE3, 0x5B, 0x56, 0x7C, 0x86, 0x4B,
+    0x91, 0xF4, 0x7F, 0x50, 0xAE, 0x72, 0x5A, 0xE2, 0xDE, 0x53, 0xBC, 0xFB,
+    0xBA, 0xF2, 0x84, 0xDA, 0x00, 0x00, 0x00, 0x00,
+];
+const GENESIS_HASH_SIGNET: [u8; 32] = [
+    0xF6, 0x1E, 0xEE, 0x3B, 0x63, 0xA3, 0x80, 0xA4, 0x77, 0xA0, 0x63, 0xAF,
+    0x32, 0xB2, 0xBB, 0xC9, 0x7C, 0x9F, 0xF9, 0xF0, 0x1F, 0x2C, 0x42, 0x25,
+    0xE9, 0x73, 0x98, 0x81, 0x08, 0x00, 0x00, 0x00,
+];
+const GENESIS_HASH_REGTEST: [u8; 32] = [
+    0x06, 0x22, 0x6E, 0x46, 0x11, 0x1A, 0x0B, 0x59, 0xCA, 0xAF, 0x12, 0x60,
+    0x43, 0xEB, 0x5B, 0xBF, 0x28, 0xC3, 0x4F, 0x3A, 0x5E, 0x33, 0x2A, 0x1F,
+    0xC7, 0xB2, 0xB7, 0x3C, 0xF1, 0x88, 0x91, 0x0F,
+];
+// SHA256 hash of `SIGNET_DEFAULT_CHALLENGE` script
+const SIGNET_DEFAULT_CHALLENGE_HASH: [u8; 32] = [
+    0xE7, 0xCA, 0x5A, 0x64, 0xC7, 0x91, 0x22, 0xC3, 0x34, 0x47, 0x8A, 0xBD,
+    0x97, 0x55, 0xF4, 0x43, 0x88, 0xBF, 0x5E, 0x73, 0x86, 0x3D, 0x06, 0xA3,
+    0xE3, 0xC2, 0xD8, 0x1F, 0x71, 0xF1, 0x2E, 0x24,
+];
+
+/// Errors happening during parsing string representation of [`Chain`]
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum ChainParseError {
+    /// unknown chain name `{0}`; it must be one of `bitcoin`, `testnet`,
+    /// `testnet4`, `signet`, `signet:<challenge hash>`, `regtest` or a
+    /// hexadecimal genesis block hash
+    UnknownChain(String),
+}
+
+/// Bitcoin chain identified by its genesis block hash. Used to prevent
+/// seals and commitments defined on one chain from being verified against
+/// another one.
+///
+/// Since all signets share the same genesis block, signets are additionally
+/// identified by SHA256 hash of their block challenge script.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display)]
+pub enum Chain {
+    /// Bitcoin mainnet
+    #[display("bitcoin")]
+    Mainnet,
+
+    /// Bitcoin testnet version 3
+    #[display("testnet")]
+    Testnet3,
+
+    /// Bitcoin testnet version 4 (BIP-94)
+    #[display("testnet4")]
+    Testnet4,
+
+    /// Default bitcoin signet
+    #[display("signet")]
+    Signet,
+
+    /// Custom signet with a given hash of its block challenge script
+    #[display("signet:{0}")]
+    SignetCustom(sha256::Hash),
+
+    /// Bitcoin regtest
+    #[display("regtest")]
+    Regtest,
+
+    /// Other chain with a given genesis block hash
+    #[display("{0}")]
+    Other(BlockHash),
+}
+
+impl Chain {
+    /// Constructs signet chain from its block challenge script
+    pub fn signet(challenge: &Script) -> Chain {
+        Chain::SignetCustom(sha256::Hash::hash(challenge.as_bytes()))
+            .normalized()
+    }
+
+    /// Returns genesis block hash of the chain
+    pub fn genesis_hash(&self) -> BlockHash {
+        BlockHash::from_inner(match self {
+            Chain::Mainnet => GENESIS_HASH_MAINNET,
+            Chain::Testnet3 => GENESIS_HASH_TESTNET3,
+            Chain::Testnet4 => GENESIS_HASH_TESTNET4,
+            Chain::Signet | Chain::SignetCustom(_) => GENESIS_HASH_SIGNET,
+            Chain::Regtest => GENESIS_HASH_REGTEST,
+            Chain::Other(genesis_hash) => return *genesis_hash,
+        })
+    }
+
+    /// Returns SHA256 hash of the block challenge script for signet chains
+    pub fn signet_challenge_hash(&self) -> Option<sha256::Hash> {
+        match self {
+            Chain::Signet => {
+                Some(sha256::Hash::from_inner(SIGNET_DEFAULT_CHALLENGE_HASH))
+            }
+            Chain::SignetCustom(challenge_hash) => Some(*challenge_hash),
+            _ => None,
+        }
+    }
+
+    /// Replaces custom signet using the default challenge with
+    /// [`Chain::Signet`]
+    fn normalized(self) -> Chain {
+        match self {
+            Chain::SignetCustom(challenge_hash)
+                if challenge_hash.into_inner()
+                    == SIGNET_DEFAULT_CHALLENGE_HASH =>
+            {
+                Chain::Signet
+            }
+            chain => chain,
+        }
+    }
+
+    /// Detects chain from its genesis block hash. Signet genesis hash is
+    /// always detected as the default [`Chain::Signet`].
+    pub fn from_genesis_hash(genesis_hash: BlockHash) -> Chain {
+        match genesis_hash.into_inner() {
+            GENESIS_HASH_MAINNET => Chain::Mainnet,
+            GENESIS_HASH_TESTNET3 => Chain::Testnet3,
+            GENESIS_HASH_TESTNET4 => Chain::Testnet4,
+            GENESIS_HASH_SIGNET => Chain::Signet,
+            GENESIS_HASH_REGTEST => Chain::Regtest,
+            _ => Chain::Other(genesis_hash),
+        }
+    }
+}
+
+impl From<Network> for Chain {
+    fn from(network: Network) -> Self {
+        match network {
+            Network::Bitcoin => Chain::Mainnet,
+            Network::Testnet => Chain::Testnet3,
+            Network::Signet => Chain::Signet,
+            Network::Regtest => Chain::Regtest,
+        }
+    }
+}
+
+impl TryFrom<Chain> for Network {
+    type Error = Chain;
+
+    /// Converts chain into a bitcoin network, returning the original chain
+    /// as an error for [`Chain::Other`]. Testnet4 and custom signets are
+    /// converted into networks sharing their address and key formats.
+    fn try_from(chain: Chain) -> Result<Self, Self::Error> {
+        Ok(match chain {
+            Chain::Mainnet => Network::Bitcoin,
+            Chain::Testnet3 | Chain::Testnet4 => Network::Testnet,
+            Chain::Signet | Chain::SignetCustom(_) => Network::Signet,
+            Chain::Regtest => Network::Regtest,
+            Chain::Other(_) => return Err(chain),
+        })
+    }
+}
+
+impl FromStr for Chain {
+    type Err = ChainParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_lowercase().as_str() {
+            "bitcoin" | "mainnet" => Chain::Mainnet,
+            "testnet" | "testnet3" => Chain::Testnet3,
+            "testnet4" => Chain::Testnet4,
+            "signet" => Chain::Signet,
+            "regtest" => Chain::Regtest,
+            other if other.starts_with("signet:") => Chain::SignetCustom(
+                sha256::Hash::from_hex(&other["signet:".len()..])
+                    .map_err(|_| ChainParseError::UnknownChain(s.to_owned()))?,
+            )
+            .normalized(),
+            other => Chain::from_genesis_hash(
+                BlockHash::from_str(other)
+                    .map_err(|_| ChainParseError::UnknownChain(s.to_owned()))?,
+            ),
+        })
+    }
+}
+
+// Chains are consensus-encoded as their genesis block hashes, followed by
+// the challenge script hash for signets
+impl StrictEncode for Chain {
+    fn strict_encode<E: io::Write>(
+        &self,
+        mut e: E,
+    ) -> Result<usize, strict_encoding::Error> {
+        let mut len = self.genesis_hash().strict_encode(&mut e)?;
+        if let Some(challenge_hash) = self.signet_challenge_hash() {
+            len += challenge_hash.strict_encode(&mut e)?;
+        }
+        Ok(len)
+    }
+}
+
+impl StrictDecode for Chain {
+    fn strict_decode<D: io::Read>(
+        mut d: D,
+    ) -> Result<Self, strict_encoding::Error> {
+        let chain = Chain::from_genesis_hash(BlockHash::strict_decode(&mut d)?);
+        Ok(match chain {
+            Chain::Signet => {
+                Chain::SignetCustom(sha256::Hash::strict_decode(d)?)
+                    .normalized()
+            }
+            chain => chain,
+        })
+    }
+}
+
+// Generated chains are normalized, so they survive string and strict encoding
+// round trips
+#[cfg(feature = "test-utils")]
+impl<'a> arbitrary::Arbitrary<'a> for Chain {
+    fn arbitrary(
+        u: &mut arbitrary::Unstructured<'a>,
+    ) -> arbitrary::Result<Self> {
+        Ok(match u.int_in_range(0..=6)? {
+            0 => Chain::Mainnet,
+            1 => Chain::Testnet3,
+            2 => Chain::Testnet4,
+            3 => Chain::Signet,
+            4 => Chain::SignetCustom(sha256::Hash::from_inner(
+                u.arbitrary::<[u8; 32]>()?,
+            )),
+            5 => Chain::Regtest,
+            _ => {
+                Chain::Other(BlockHash::from_inner(u.arbitrary::<[u8; 32]>()?))
+            }
+        }
+        .normalized())
+    }
+}
+
+#[cfg(feature = "serde")]
+dbc::serde_str_or_strict!(Chain, "chain name or genesis block hash");
+
+#[cfg(test)]
+mod test {
+    use bitcoin::blockdata::constants::genesis_block;
+    use strict_encoding::{strict_deserialize, strict_serialize};
+
+    use super::*;
+
+    #[test]
+    fn test_chain_genesis() {
+        let mainnet = BlockHash::from_hex(
+            "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f",
+        )
+        .unwrap();
+        assert_eq!(Chain::Mainnet.genesis_hash(), mainnet);
+        assert_eq!(Chain::from_genesis_hash(mainnet), Chain::Mainnet);
+
+        for chain in [
+            Chain::Mainnet,
+            Chain::Testnet3,
+            Chain::Signet,
+            Chain::Regtest,
+        ] {
+            assert_eq!(Chain::from_genesis_hash(chain.genesis_hash()), chain);
+            let network = Network::try_from(chain).unwrap();
+            assert_eq!(Chain::from(network), chain);
+            assert_eq!(
+                chain.genesis_hash(),
+                genesis_block(network).block_hash()
+            );
+        }
+
+        let testnet4 = BlockHash::from_hex(TESTNET4_GENESIS_HASH).unwrap();
+        assert_eq!(Chain::Testnet4.genesis_hash(), testnet4);
+        assert_eq!(Chain::from_genesis_hash(testnet4), Chain::Testnet4);
+        assert_eq!(Network::try_from(Chain::Testnet4), Ok(Network::Testnet));
+
+        let other = Chain::Other(BlockHash::default());
+        assert_eq!(other.genesis_hash(), BlockHash::default());
+        assert_eq!(Chain::from_genesis_hash(BlockHash::default()), other);
+        assert_eq!(Network::try_from(other), Err(other));
+    }
+
+    #[test]
+    fn test_chain_signet() {
+        let default =
+            Script::from(Vec::from_hex(SIGNET_DEFAULT_CHALLENGE).unwrap());
+        assert_eq!(Chain::signet(&default), Chain::Signet);
+
+        let challenge = Script::from(vec![0x51]);
+        let custom = Chain::signet(&challenge);
+        assert_eq!(custom, Chain::SignetCustom(sha256::Hash::hash(&[0x51])));
+        assert_ne!(custom, Chain::Signet);
+        assert_eq!(custom.genesis_hash(), Chain::Signet.genesis_hash());
+        assert_eq!(Network::try_from(custom), Ok(Network::Signet));
+        assert_eq!(Chain::Mainnet.signet_challenge_hash(), None);
+    }
+
+    #[test]
+    fn test_chain_str() {
+        for chain in [
+            Chain::Mainnet,
+            Chain::Testnet3,
+            Chain::Testnet4,
+            Chain::Signet,
+            Chain::SignetCustom(sha256::Hash::hash(&[0x51])),
+            Chain::Regtest,
+            Chain::Other(BlockHash::default()),
+        ] {
+            assert_eq!(Chain::from_str(&chain.to_string()), Ok(chain));
+        }
+        assert_eq!(Chain::from_str("mainnet"), Ok(Chain::Mainnet));
+        assert_eq!(Chain::from_str("Testnet3"), Ok(Chain::Testnet3));
+        assert_eq!(
+            Chain::from_str(&Chain::Regtest.genesis_hash().to_string()),
+            Ok(Chain::Regtest)
+        );
+        assert_eq!(
+            Chain::from_str(&format!(
+                "signet:{}",
+                Chain::Signet.signet_challenge_hash().unwrap()
+            )),
+            Ok(Chain::Signet)
+        );
+        assert_eq!(
+            Chain::from_str("signet:00"),
+            Err(ChainParseError::UnknownChain(s!("signet:00")))
+        );
+        assert_eq!(
+            Chain::from_str("litecoin"),
+            Err(ChainParseError::UnknownChain(s!("litecoin")))
+        );
+    }
+
+    #[test]
+    fn test_chain_strict_encoding() {
+        let data = strict_serialize(&Chain::Mainnet).unwrap();
+        assert_eq!(data, Chain::Mainnet.genesis_hash().to_vec());
+        assert_eq!(strict_deserialize::<Chain>(&data).unwrap(), Chain::Mainnet);
+
+        for chain in [
+            Chain::Testnet4,
+            Chain::Signet,
+            Chain::SignetCustom(sha256::Hash::hash(&[0x51])),
+            Chain::Other(BlockHash::default()),
+        ] {
+            let data = strict_serialize(&chain).unwrap();
+            assert_eq!(strict_deserialize::<Chain>(&data).unwrap(), chain);
+        }
+
+        let data = strict_serialize(&Chain::Signet).unwrap();
+        assert_eq!(data.len(), 64);
+        assert_eq!(&data[..32], &Chain::Signet.genesis_hash()[..]);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_chain_serde() {
+        for chain in [
+            Chain::Mainnet,
+            Chain::Testnet3,
+            Chain::Testnet4,
+            Chain::Signet,
+            Chain::SignetCustom(sha256::Hash::hash(&[0x51])),
+            Chain::Regtest,
+            Chain::Other(BlockHash::default()),
+        ] {
+            let json = serde_json::to_string(&chain).unwrap();
+            assert_eq!(json, format!("\"{}\"", chain));
+            assert_eq!(serde_json::from_str::<Chain>(&json).unwrap(), chain);
+
+            let data = bincode::serialize(&chain).unwrap();
+            assert_eq!(
+                bincode::deserialize::<Vec<u8>>(&data).unwrap(),
+                strict_serialize(&chain).unwrap()
+            );
+            assert_eq!(bincode::deserialize::<Chain>(&data).unwrap(), chain);
+        }
+    }
+}
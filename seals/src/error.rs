@@ -33,6 +33,10 @@ pub enum Error {
 
     /// Resolver probably lies and can't be trusted
     ResolverLying,
+
+    /// Seal is defined on a chain different from the one served by the
+    /// resolver
+    ChainMismatch,
 }
 
 impl From<dbc::Error> for Error {
@@ -16,6 +16,8 @@
 // Coding conventions
 #![recursion_limit = "256"]
 #![deny(dead_code, /* missing_docs, */ warnings)]
+// Required by the code generated with `strict_encoding` derive macros
+#![allow(clippy::init_numbered_fields)]
 
 #[macro_use]
 extern crate amplify;
@@ -29,11 +31,21 @@ extern crate async_trait;
 extern crate serde_crate as serde;
 
 mod blind;
+mod chain;
 mod error;
+mod seal_point;
 mod txout_seal;
 mod txout_witness;
 
 pub use blind::{OutpointHash, OutpointReveal, ParseError};
+pub use chain::{
+    Chain, ChainParseError, SIGNET_DEFAULT_CHALLENGE, TESTNET4_GENESIS_HASH,
+};
 pub use error::Error;
+pub use seal_point::{
+    verify_seal_chain, ChainSeal, SealChainError, SealPoint,
+};
+#[cfg(feature = "async")]
+pub use txout_seal::AsyncTxResolve;
 pub use txout_seal::{TxResolve, TxoutSeal};
 pub use txout_witness::{InnerWitness, OuterWitness, Witness};
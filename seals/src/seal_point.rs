@@ -0,0 +1,579 @@
+// BP Core Library implementing LNP/BP specifications & standards related to
+// bitcoin protocol
+//
+// Written in 2020-2022 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the Apache 2.0 License
+// along with this software.
+// If not, see <https://opensource.org/licenses/Apache-2.0>.
+
+use std::str::FromStr;
+
+use bitcoin::{OutPoint, Transaction, Txid};
+use dbc::Anchor;
+
+use crate::{Chain, ParseError};
+
+/// Transaction output seal definition, which may point either to an output of
+/// an already known transaction, or to an output of the witness transaction
+/// closing some other seal, which txid is not known at the moment of the seal
+/// definition.
+///
+/// String representation of the seal has form `<txid>:<vout>` for
+/// [`SealPoint::TxOutpoint`] and `~:<vout>` for [`SealPoint::WitnessVout`].
+#[derive(
+    Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display, From
+)]
+#[derive(StrictEncode, StrictDecode)]
+pub enum SealPoint {
+    /// Seal defined by an output of an already known transaction
+    #[from]
+    #[display("{0}")]
+    TxOutpoint(OutPoint),
+
+    /// Seal defined by an output number of the witness transaction, which
+    /// closes some other seal and will contain the commitment
+    #[display("~:{0}")]
+    WitnessVout(u32),
+}
+
+impl SealPoint {
+    /// Returns transaction output number used by the seal
+    #[inline]
+    pub fn vout(&self) -> u32 {
+        match self {
+            SealPoint::TxOutpoint(outpoint) => outpoint.vout,
+            SealPoint::WitnessVout(vout) => *vout,
+        }
+    }
+
+    /// Returns transaction outpoint for the seal, if it is already known
+    #[inline]
+    pub fn outpoint(&self) -> Option<OutPoint> {
+        match self {
+            SealPoint::TxOutpoint(outpoint) => Some(*outpoint),
+            SealPoint::WitnessVout(_) => None,
+        }
+    }
+
+    /// Resolves seal into a transaction outpoint using `witness_txid` as the
+    /// id of the witness transaction for [`SealPoint::WitnessVout`] seals.
+    /// Seals defined with [`SealPoint::TxOutpoint`] are returned unchanged.
+    #[inline]
+    pub fn resolve(&self, witness_txid: Txid) -> OutPoint {
+        match self {
+            SealPoint::TxOutpoint(outpoint) => *outpoint,
+            SealPoint::WitnessVout(vout) => OutPoint::new(witness_txid, *vout),
+        }
+    }
+}
+
+impl FromStr for SealPoint {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut split = s.split(':');
+        match (split.next(), split.next(), split.next()) {
+            (Some("~"), Some(vout), None) => Ok(SealPoint::WitnessVout(
+                vout.parse().map_err(|_| ParseError::WrongVout)?,
+            )),
+            (Some("_"), ..) | (Some(""), ..) => Err(ParseError::TxidRequired),
+            (Some(txid), Some(vout), None) => {
+                Ok(SealPoint::TxOutpoint(OutPoint::new(
+                    txid.parse().map_err(|_| ParseError::WrongTxid)?,
+                    vout.parse().map_err(|_| ParseError::WrongVout)?,
+                )))
+            }
+            _ => Err(ParseError::WrongStructure),
+        }
+    }
+}
+
+/// Seal definition bound to a specific chain.
+///
+/// String representation of the seal has form `<chain>:<seal>`, where the
+/// chain is given by its name or genesis block hash according to [`Chain`]
+/// and the seal follows [`SealPoint`] representation, for instance
+/// `testnet:<txid>:<vout>`.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display)]
+#[derive(StrictEncode, StrictDecode)]
+#[display("{chain}:{seal}")]
+pub struct ChainSeal {
+    /// Chain on which the seal is defined
+    pub chain: Chain,
+
+    /// Seal definition
+    pub seal: SealPoint,
+}
+
+impl ChainSeal {
+    /// Constructs seal definition for a given chain
+    #[inline]
+    pub fn new(chain: Chain, seal: SealPoint) -> Self { Self { chain, seal } }
+}
+
+impl FromStr for ChainSeal {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Chain names may contain `:` themselves (like custom signets), so
+        // the seal is taken from the last two components
+        let mut split = s.rsplitn(3, ':');
+        match (split.next(), split.next(), split.next()) {
+            (Some(vout), Some(txid), Some(chain)) => Ok(ChainSeal {
+                chain: chain.parse()?,
+                seal: SealPoint::from_str(&format!("{}:{}", txid, vout))?,
+            }),
+            _ => Err(ParseError::WrongStructure),
+        }
+    }
+}
+
+/// Errors detected during validation of the seal closing history with
+/// [`verify_seal_chain`]. Each variant contains the index of the first link
+/// breaking the chain.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum SealChainError {
+    /// seal #{0} is defined as an output of a witness transaction, but there
+    /// is no previous witness transaction to resolve it
+    UnresolvedSeal(usize),
+
+    /// seal #{0} at {1} is not defined by the previous witness transaction
+    NotDefined(usize, OutPoint),
+
+    /// seal #{0} points to output {1} which is absent in the previous witness
+    /// transaction
+    NoOutput(usize, u32),
+
+    /// witness transaction #{0} does not close seal {1}
+    NotClosed(usize, OutPoint),
+
+    /// anchor #{0} does not belong to the witness transaction
+    AnchorMismatch(usize),
+}
+
+/// Validates the history of seals, each of which is closed by a witness
+/// transaction holding a commitment described by the anchor.
+///
+/// The first seal must be a [`SealPoint::TxOutpoint`] and may point to any
+/// transaction output. Each of the following seals must be defined by the
+/// witness transaction of the previous link: [`SealPoint::WitnessVout`] seals
+/// are resolved against it, and [`SealPoint::TxOutpoint`] seals must point to
+/// one of its outputs. Each anchor must be created for the witness
+/// transaction of its link.
+///
+/// The function checks only the seal closing; verification of the messages
+/// committed by each of the anchors is protocol-specific and must be done
+/// with [`dbc::Anchor::verify`].
+pub fn verify_seal_chain<'a>(
+    links: impl IntoIterator<Item = (SealPoint, &'a Transaction, &'a Anchor)>,
+) -> Result<(), SealChainError> {
+    let mut prev_witness: Option<&Transaction> = None;
+    for (index, (seal, witness, anchor)) in links.into_iter().enumerate() {
+        let outpoint = match (seal, prev_witness) {
+            (SealPoint::TxOutpoint(outpoint), None) => outpoint,
+            (SealPoint::WitnessVout(_), None) => {
+                return Err(SealChainError::UnresolvedSeal(index))
+            }
+            (seal, Some(prev)) => {
+                let outpoint = seal.resolve(prev.txid());
+                if outpoint.txid != prev.txid() {
+                    return Err(SealChainError::NotDefined(index, outpoint));
+                }
+                if outpoint.vout as usize >= prev.output.len() {
+                    return Err(SealChainError::NoOutput(index, outpoint.vout));
+                }
+                outpoint
+            }
+        };
+        if !witness
+            .input
+            .iter()
+            .any(|txin| txin.previous_output == outpoint)
+        {
+            return Err(SealChainError::NotClosed(index, outpoint));
+        }
+        if anchor.txid != witness.txid() {
+            return Err(SealChainError::AnchorMismatch(index));
+        }
+        prev_witness = Some(witness);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "test-utils")]
+impl<'a> arbitrary::Arbitrary<'a> for SealPoint {
+    fn arbitrary(
+        u: &mut arbitrary::Unstructured<'a>,
+    ) -> arbitrary::Result<Self> {
+        use bitcoin::hashes::Hash;
+
+        Ok(if u.arbitrary()? {
+            SealPoint::TxOutpoint(OutPoint::new(
+                Txid::from_inner(u.arbitrary()?),
+                u.arbitrary()?,
+            ))
+        } else {
+            SealPoint::WitnessVout(u.arbitrary()?)
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+dbc::serde_str_or_strict!(
+    SealPoint,
+    "seal string in `<txid>:<vout>` or `~:<vout>` form"
+);
+
+#[cfg(feature = "test-utils")]
+impl<'a> arbitrary::Arbitrary<'a> for ChainSeal {
+    fn arbitrary(
+        u: &mut arbitrary::Unstructured<'a>,
+    ) -> arbitrary::Result<Self> {
+        Ok(ChainSeal::new(u.arbitrary()?, u.arbitrary()?))
+    }
+}
+
+#[cfg(feature = "serde")]
+dbc::serde_str_or_strict!(
+    ChainSeal,
+    "seal string in `<chain>:<txid>:<vout>` or `<chain>:~:<vout>` form"
+);
+
+#[cfg(test)]
+mod test {
+    use amplify::DumbDefault;
+    use bitcoin::hashes::hex::FromHex;
+    use bitcoin::{TxIn, TxOut};
+    use commit_verify::multi_commit::MultiCommitBlock;
+    #[cfg(feature = "test-utils")]
+    use dbc::test_utils::strategy;
+    #[cfg(feature = "test-utils")]
+    use proptest::prelude::*;
+    use strict_encoding::{strict_deserialize, strict_serialize};
+
+    use super::*;
+    use crate::ChainParseError;
+
+    #[test]
+    fn test_seal_point_resolve() {
+        let txid = Txid::from_hex(
+            "646ca5c1062619e2a2d60771c9dfd820551fb773e4dc8c4ed67965a8d1fae839",
+        )
+        .unwrap();
+        let witness_txid = Txid::from_hex(
+            "3f0cc1f6a0165b8d1906f2a7f6bec256fa1d3d2b6fe0dcb58f1a54b8a2e48c8b",
+        )
+        .unwrap();
+
+        let seal = SealPoint::from(OutPoint::new(txid, 2));
+        assert_eq!(seal.vout(), 2);
+        assert_eq!(seal.outpoint(), Some(OutPoint::new(txid, 2)));
+        assert_eq!(seal.resolve(witness_txid), OutPoint::new(txid, 2));
+
+        let seal = SealPoint::WitnessVout(1);
+        assert_eq!(seal.vout(), 1);
+        assert_eq!(seal.outpoint(), None);
+        assert_eq!(seal.resolve(witness_txid), OutPoint::new(witness_txid, 1));
+    }
+
+    #[test]
+    fn test_seal_point_str() {
+        let txid = Txid::from_hex(
+            "646ca5c1062619e2a2d60771c9dfd820551fb773e4dc8c4ed67965a8d1fae839",
+        )
+        .unwrap();
+
+        let seal = SealPoint::from(OutPoint::new(txid, 21));
+        let s = seal.to_string();
+        assert_eq!(
+            &s,
+            "646ca5c1062619e2a2d60771c9dfd820551fb773e4dc8c4ed67965a8d1fae839:\
+             21"
+        );
+        assert_eq!(SealPoint::from_str(&s), Ok(seal));
+
+        let seal = SealPoint::WitnessVout(2);
+        assert_eq!(&seal.to_string(), "~:2");
+        assert_eq!(SealPoint::from_str("~:2"), Ok(seal));
+
+        assert_eq!(SealPoint::from_str("~:-1"), Err(ParseError::WrongVout));
+        assert_eq!(SealPoint::from_str("~:"), Err(ParseError::WrongVout));
+        assert_eq!(SealPoint::from_str(":2"), Err(ParseError::TxidRequired));
+        assert_eq!(SealPoint::from_str("_:2"), Err(ParseError::TxidRequired));
+        assert_eq!(SealPoint::from_str("rvgbdg:2"), Err(ParseError::WrongTxid));
+        assert_eq!(
+            SealPoint::from_str(
+                "646ca5c1062619e2a2d60771c9dfd820551fb773e4dc8c4ed67965a8d1fae839:0x1"
+            ),
+            Err(ParseError::WrongVout)
+        );
+        assert_eq!(
+            SealPoint::from_str(
+                "646ca5c1062619e2a2d60771c9dfd820551fb773e4dc8c4ed67965a8d1fae839"
+            ),
+            Err(ParseError::WrongStructure)
+        );
+        assert_eq!(
+            SealPoint::from_str("~:1:2"),
+            Err(ParseError::WrongStructure)
+        );
+    }
+
+    #[test]
+    fn test_seal_point_strict_encoding() {
+        let txid = Txid::from_hex(
+            "646ca5c1062619e2a2d60771c9dfd820551fb773e4dc8c4ed67965a8d1fae839",
+        )
+        .unwrap();
+
+        let seal = SealPoint::from(OutPoint::new(txid, 2));
+        let data = Vec::<u8>::from_hex(concat!(
+            "00",
+            "39e8fad1a86579d64e8cdce473b71f5520d8dfc97107d6a2e2192606c1a56c64",
+            "02000000"
+        ))
+        .unwrap();
+        assert_eq!(strict_serialize(&seal).unwrap(), data);
+        assert_eq!(strict_deserialize::<SealPoint>(&data).unwrap(), seal);
+
+        let seal = SealPoint::WitnessVout(2);
+        let data = Vec::<u8>::from_hex("0102000000").unwrap();
+        assert_eq!(strict_serialize(&seal).unwrap(), data);
+        assert_eq!(strict_deserialize::<SealPoint>(&data).unwrap(), seal);
+    }
+
+    #[test]
+    fn test_chain_seal_str() {
+        let txid = Txid::from_hex(
+            "646ca5c1062619e2a2d60771c9dfd820551fb773e4dc8c4ed67965a8d1fae839",
+        )
+        .unwrap();
+
+        let seal = ChainSeal::new(
+            Chain::Testnet3,
+            SealPoint::from(OutPoint::new(txid, 21)),
+        );
+        let s = seal.to_string();
+        assert_eq!(
+            &s,
+            "testnet:\
+             646ca5c1062619e2a2d60771c9dfd820551fb773e4dc8c4ed67965a8d1fae839:\
+             21"
+        );
+        assert_eq!(ChainSeal::from_str(&s), Ok(seal));
+
+        for chain in [
+            Chain::Mainnet,
+            Chain::SignetCustom(bitcoin::hashes::sha256::Hash::default()),
+            Chain::Other(Default::default()),
+        ] {
+            let seal = ChainSeal::new(chain, SealPoint::WitnessVout(2));
+            assert_eq!(ChainSeal::from_str(&seal.to_string()), Ok(seal));
+        }
+        assert_eq!(
+            ChainSeal::from_str("bitcoin:~:2"),
+            Ok(ChainSeal::new(Chain::Mainnet, SealPoint::WitnessVout(2)))
+        );
+
+        assert_eq!(ChainSeal::from_str("~:2"), Err(ParseError::WrongStructure));
+        assert_eq!(
+            ChainSeal::from_str("bitcoin:~:x"),
+            Err(ParseError::WrongVout)
+        );
+        assert_eq!(
+            ChainSeal::from_str("litecoin:~:2"),
+            Err(ParseError::WrongChain(ChainParseError::UnknownChain(s!(
+                "litecoin"
+            ))))
+        );
+    }
+
+    #[test]
+    fn test_chain_seal_strict_encoding() {
+        let seal = ChainSeal::new(Chain::Regtest, SealPoint::WitnessVout(2));
+        let data = strict_serialize(&seal).unwrap();
+        assert_eq!(&data[..32], &Chain::Regtest.genesis_hash()[..]);
+        assert_eq!(&data[32..], &[0x01, 0x02, 0x00, 0x00, 0x00]);
+        assert_eq!(strict_deserialize::<ChainSeal>(&data).unwrap(), seal);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_seal_point_serde() {
+        let txid = Txid::from_hex(
+            "646ca5c1062619e2a2d60771c9dfd820551fb773e4dc8c4ed67965a8d1fae839",
+        )
+        .unwrap();
+
+        for seal in [
+            SealPoint::from(OutPoint::new(txid, 2)),
+            SealPoint::WitnessVout(2),
+        ] {
+            let json = serde_json::to_string(&seal).unwrap();
+            assert_eq!(json, format!("\"{}\"", seal));
+            assert_eq!(serde_json::from_str::<SealPoint>(&json).unwrap(), seal);
+
+            let data = bincode::serialize(&seal).unwrap();
+            assert_eq!(
+                bincode::deserialize::<Vec<u8>>(&data).unwrap(),
+                strict_serialize(&seal).unwrap()
+            );
+            assert_eq!(bincode::deserialize::<SealPoint>(&data).unwrap(), seal);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_chain_seal_serde() {
+        let txid = Txid::from_hex(
+            "646ca5c1062619e2a2d60771c9dfd820551fb773e4dc8c4ed67965a8d1fae839",
+        )
+        .unwrap();
+
+        for seal in [
+            ChainSeal::new(
+                Chain::Testnet3,
+                SealPoint::from(OutPoint::new(txid, 21)),
+            ),
+            ChainSeal::new(
+                Chain::SignetCustom(bitcoin::hashes::sha256::Hash::default()),
+                SealPoint::WitnessVout(2),
+            ),
+        ] {
+            let json = serde_json::to_string(&seal).unwrap();
+            assert_eq!(json, format!("\"{}\"", seal));
+            assert_eq!(serde_json::from_str::<ChainSeal>(&json).unwrap(), seal);
+
+            let data = bincode::serialize(&seal).unwrap();
+            assert_eq!(
+                bincode::deserialize::<Vec<u8>>(&data).unwrap(),
+                strict_serialize(&seal).unwrap()
+            );
+            assert_eq!(bincode::deserialize::<ChainSeal>(&data).unwrap(), seal);
+        }
+    }
+
+    #[cfg(feature = "test-utils")]
+    proptest! {
+        #[test]
+        fn test_chain_seal_arbitrary(seal in strategy::<ChainSeal>()) {
+            prop_assert_eq!(
+                ChainSeal::from_str(&seal.to_string()),
+                Ok(seal)
+            );
+            let data = strict_serialize(&seal).unwrap();
+            prop_assert_eq!(
+                strict_deserialize::<ChainSeal>(&data).unwrap(),
+                seal
+            );
+        }
+    }
+
+    fn witness(spent: OutPoint, outputs: usize) -> Transaction {
+        Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![TxIn {
+                previous_output: spent,
+                ..Default::default()
+            }],
+            output: vec![TxOut::default(); outputs],
+        }
+    }
+
+    fn anchor(witness: &Transaction) -> Anchor {
+        Anchor {
+            txid: witness.txid(),
+            lnpbp4_proof: MultiCommitBlock {
+                commitments: vec![],
+                entropy: None,
+            },
+            dbc_proof: dbc::Proof::dumb_default(),
+            spv_proof: None,
+        }
+    }
+
+    #[test]
+    fn test_seal_chain() {
+        let txid = Txid::from_hex(
+            "646ca5c1062619e2a2d60771c9dfd820551fb773e4dc8c4ed67965a8d1fae839",
+        )
+        .unwrap();
+        let genesis = OutPoint::new(txid, 0);
+        let tx1 = witness(genesis, 2);
+        let tx2 = witness(OutPoint::new(tx1.txid(), 1), 1);
+        let tx3 = witness(OutPoint::new(tx2.txid(), 0), 1);
+        let (a1, a2, a3) = (anchor(&tx1), anchor(&tx2), anchor(&tx3));
+
+        assert_eq!(verify_seal_chain(vec![]), Ok(()));
+        assert_eq!(
+            verify_seal_chain(vec![
+                (SealPoint::from(genesis), &tx1, &a1),
+                (SealPoint::WitnessVout(1), &tx2, &a2),
+                (SealPoint::from(OutPoint::new(tx2.txid(), 0)), &tx3, &a3),
+            ]),
+            Ok(())
+        );
+
+        assert_eq!(
+            verify_seal_chain(vec![(SealPoint::WitnessVout(0), &tx1, &a1)]),
+            Err(SealChainError::UnresolvedSeal(0))
+        );
+        assert_eq!(
+            verify_seal_chain(vec![
+                (SealPoint::from(genesis), &tx1, &a1),
+                (SealPoint::WitnessVout(2), &tx2, &a2),
+            ]),
+            Err(SealChainError::NoOutput(1, 2))
+        );
+        assert_eq!(
+            verify_seal_chain(vec![
+                (SealPoint::from(genesis), &tx1, &a1),
+                (SealPoint::from(OutPoint::new(tx1.txid(), 5)), &tx2, &a2),
+            ]),
+            Err(SealChainError::NoOutput(1, 5))
+        );
+        assert_eq!(
+            verify_seal_chain(vec![
+                (SealPoint::from(genesis), &tx1, &a1),
+                (SealPoint::WitnessVout(0), &tx2, &a2),
+            ]),
+            Err(SealChainError::NotClosed(1, OutPoint::new(tx1.txid(), 0)))
+        );
+        assert_eq!(
+            verify_seal_chain(vec![
+                (SealPoint::from(genesis), &tx1, &a1),
+                (SealPoint::WitnessVout(1), &tx3, &a3),
+            ]),
+            Err(SealChainError::NotClosed(1, OutPoint::new(tx1.txid(), 1)))
+        );
+
+        // Explicit seal unrelated to the previous witness, even though the
+        // witness transaction closes it
+        let unrelated = OutPoint::new(txid, 1);
+        let tx4 = witness(unrelated, 1);
+        assert_eq!(
+            verify_seal_chain(vec![
+                (SealPoint::from(genesis), &tx1, &a1),
+                (SealPoint::from(unrelated), &tx4, &anchor(&tx4)),
+            ]),
+            Err(SealChainError::NotDefined(1, unrelated))
+        );
+
+        assert_eq!(
+            verify_seal_chain(vec![
+                (SealPoint::from(genesis), &tx1, &a1),
+                (SealPoint::WitnessVout(1), &tx2, &a1),
+            ]),
+            Err(SealChainError::AnchorMismatch(1))
+        );
+    }
+}
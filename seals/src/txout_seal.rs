@@ -21,10 +21,9 @@ use dbc::{Container, TxCommitment, TxContainer, TxSupplement};
 use single_use_seals::SealMediumAsync;
 use single_use_seals::{SealMedium, SingleUseSeal};
 
-use super::{Error, Witness};
+use super::{Chain, ChainSeal, Error, SealPoint, Witness};
 
 // TODO: #8 Implement proper operations with SealMedium
-// TODO: #9 Do asyncronous version
 
 pub struct TxoutSeal<'a, R>
 where
@@ -32,6 +31,7 @@ where
     Self: 'a,
 {
     seal_definition: OutPoint,
+    chain: Option<Chain>,
     resolver: &'a R,
 }
 
@@ -43,9 +43,157 @@ where
     pub fn new(seal_definition: OutPoint, resolver: &'a R) -> Self {
         Self {
             seal_definition,
+            chain: None,
             resolver,
         }
     }
+
+    /// Constructs seal defined on a specific chain. Closing and verification
+    /// of the seal fail with [`Error::ChainMismatch`] unless the resolver
+    /// reports that it serves the same chain.
+    pub fn with_chain(
+        seal_definition: OutPoint,
+        chain: Chain,
+        resolver: &'a R,
+    ) -> Self {
+        Self {
+            seal_definition,
+            chain: Some(chain),
+            resolver,
+        }
+    }
+
+    /// Constructs seal from its chain-bound definition, if the definition
+    /// refers to an existing transaction output. Seals defined with
+    /// [`SealPoint::WitnessVout`] must be resolved against the witness
+    /// transaction first, otherwise [`Error::InvalidSealDefinition`] is
+    /// returned.
+    pub fn with_chain_seal(
+        chain_seal: ChainSeal,
+        resolver: &'a R,
+    ) -> Result<Self, Error> {
+        match chain_seal.seal {
+            SealPoint::TxOutpoint(outpoint) => {
+                Ok(Self::with_chain(outpoint, chain_seal.chain, resolver))
+            }
+            SealPoint::WitnessVout(_) => Err(Error::InvalidSealDefinition),
+        }
+    }
+
+    fn check_chain(&self, resolver_chain: Option<Chain>) -> Result<(), Error> {
+        match self.chain {
+            Some(chain) if resolver_chain != Some(chain) => {
+                Err(Error::ChainMismatch)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn close_seal(&self, over: &Message) -> Result<Witness, Error> {
+        self.check_chain(TxResolve::chain(self.resolver))?;
+        let mut container = self
+            .resolver
+            .tx_container(self.seal_definition)
+            .map_err(|_| Error::ResolverError)?;
+        let tx_commitment = TxCommitment::embed_commit(&mut container, &over)?;
+        Ok(Witness(tx_commitment, container.to_proof()))
+    }
+
+    // Verification does not depend on the seal medium, so it is shared by
+    // both blocking and asynchronous versions of `SingleUseSeal::verify`,
+    // which differ only in the way the witness transaction is resolved
+    fn verify_host(
+        &self,
+        msg: &Message,
+        witness: &Witness,
+        host: Transaction,
+        supplement: TxSupplement,
+    ) -> Result<bool, Error> {
+        let found_seals = host
+            .input
+            .iter()
+            .filter(|txin| txin.previous_output == self.seal_definition);
+        if found_seals.count() != 1 {
+            return Err(Error::ResolverLying);
+        }
+        let container =
+            TxContainer::reconstruct(&witness.1, &supplement, &host)?;
+        let commitment = TxCommitment::from_inner(host);
+        Ok(commitment.verify(&container, &msg)?)
+    }
+
+    /// Verifies a batch of seals defined on the `chain` (if any) against
+    /// their witnesses, resolving all witness transactions with a single
+    /// [`TxResolve::tx_and_data_batch`] call. Returns verification results
+    /// in the same order as the seals in the batch; any resolver failure
+    /// fails the whole batch.
+    pub fn verify_batch(
+        resolver: &'a R,
+        chain: Option<Chain>,
+        batch: &[(OutPoint, &Message, &Witness)],
+    ) -> Result<Vec<bool>, Error> {
+        let seals = batch
+            .iter()
+            .map(|(seal_definition, ..)| Self {
+                seal_definition: *seal_definition,
+                chain,
+                resolver,
+            })
+            .collect::<Vec<_>>();
+        if let Some(seal) = seals.first() {
+            seal.check_chain(TxResolve::chain(resolver))?;
+        }
+        let outpoints = seals
+            .iter()
+            .map(|seal| seal.seal_definition)
+            .collect::<Vec<_>>();
+        let resolved = resolver
+            .tx_and_data_batch(&outpoints)
+            .map_err(|_| Error::ResolverError)?;
+        if resolved.len() != batch.len() {
+            return Err(Error::ResolverLying);
+        }
+        seals
+            .iter()
+            .zip(batch)
+            .zip(resolved)
+            .map(|((seal, (_, msg, witness)), (host, supplement))| {
+                seal.verify_host(msg, witness, host, supplement)
+            })
+            .collect()
+    }
+
+    fn verify_witness(
+        &self,
+        msg: &Message,
+        witness: &Witness,
+    ) -> Result<bool, Error> {
+        self.check_chain(TxResolve::chain(self.resolver))?;
+        let (host, supplement) =
+            TxResolve::tx_and_data(self.resolver, self.seal_definition)
+                .map_err(|_| Error::ResolverError)?;
+        self.verify_host(msg, witness, host, supplement)
+    }
+
+    /// Verifies that the seal was closed over `msg` with `witness`, resolving
+    /// the witness transaction with [`AsyncTxResolve`] without blocking the
+    /// executor.
+    #[cfg(feature = "async")]
+    pub async fn verify_witness_async(
+        &self,
+        msg: &Message,
+        witness: &Witness,
+    ) -> Result<bool, Error>
+    where
+        R: AsyncTxResolve + Sync,
+    {
+        self.check_chain(AsyncTxResolve::chain(self.resolver))?;
+        let (host, supplement) =
+            AsyncTxResolve::tx_and_data(self.resolver, self.seal_definition)
+                .await
+                .map_err(|_| Error::ResolverError)?;
+        self.verify_host(msg, witness, host, supplement)
+    }
 }
 
 #[cfg_attr(feature = "async", async_trait)]
@@ -63,12 +211,7 @@ where
         &self,
         over: &Self::Message,
     ) -> Result<Self::Witness, Self::Error> {
-        let mut container = self
-            .resolver
-            .tx_container(self.seal_definition)
-            .map_err(|_| Error::ResolverError)?;
-        let tx_commitment = TxCommitment::embed_commit(&mut container, &over)?;
-        Ok(Witness(tx_commitment, container.to_proof()))
+        self.close_seal(over)
     }
 
     fn verify(
@@ -77,34 +220,23 @@ where
         witness: &Self::Witness,
         _medium: &impl SealMedium<Self>,
     ) -> Result<bool, Self::Error> {
-        let (host, supplement) = self
-            .resolver
-            .tx_and_data(self.seal_definition)
-            .map_err(|_| Error::ResolverError)?;
-        let found_seals = host
-            .input
-            .iter()
-            .filter(|txin| txin.previous_output == self.seal_definition);
-        if found_seals.count() != 1 {
-            return Err(Error::ResolverLying);
-        }
-        let container =
-            TxContainer::reconstruct(&witness.1, &supplement, &host)?;
-        let commitment = TxCommitment::from_inner(host);
-        Ok(commitment.verify(&container, &msg)?)
+        self.verify_witness(msg, witness)
     }
 
+    // Requiring `AsyncTxResolve` here would break resolvers implementing
+    // `TxResolve` only once the `async` feature gets enabled; for
+    // non-blocking verification use `TxoutSeal::verify_witness_async`
     #[cfg(feature = "async")]
     async fn verify_async(
         &self,
-        _msg: &Self::Message,
-        _witness: &Self::Witness,
+        msg: &Self::Message,
+        witness: &Self::Witness,
         _medium: &impl SealMediumAsync<Self>,
     ) -> Result<bool, Self::Error>
     where
         Self: Sized + Sync + Send,
     {
-        todo!("#9 Implement verify_async")
+        self.verify_witness(msg, witness)
     }
 }
 
@@ -153,6 +285,12 @@ where
 
 pub trait TxResolve {
     type Error: std::error::Error;
+
+    /// Returns chain served by the resolver, if known. Seals bound to a
+    /// specific chain can't be closed or verified with resolvers returning
+    /// `None`.
+    fn chain(&self) -> Option<Chain> { None }
+
     fn tx_container(
         &self,
         outpoint: OutPoint,
@@ -161,4 +299,429 @@ pub trait TxResolve {
         &self,
         outpoint: OutPoint,
     ) -> Result<(Transaction, TxSupplement), Self::Error>;
+
+    /// Resolves transactions and their supplement data for a batch of
+    /// outpoints, returning them in the same order. The default
+    /// implementation calls [`TxResolve::tx_and_data`] for each outpoint;
+    /// resolvers backed by network services should override it to combine
+    /// the requests.
+    fn tx_and_data_batch(
+        &self,
+        outpoints: &[OutPoint],
+    ) -> Result<Vec<(Transaction, TxSupplement)>, Self::Error> {
+        outpoints
+            .iter()
+            .map(|outpoint| self.tx_and_data(*outpoint))
+            .collect()
+    }
+}
+
+/// Asynchronous version of the [`TxResolve`] trait, used by
+/// [`SingleUseSeal::verify_async`] implementation for [`TxoutSeal`]
+#[cfg(feature = "async")]
+#[async_trait]
+pub trait AsyncTxResolve {
+    type Error: std::error::Error;
+
+    /// Returns chain served by the resolver, if known. Seals bound to a
+    /// specific chain can't be verified with resolvers returning `None`.
+    fn chain(&self) -> Option<Chain> { None }
+
+    async fn tx_container(
+        &self,
+        outpoint: OutPoint,
+    ) -> Result<TxContainer, Self::Error>;
+    async fn tx_and_data(
+        &self,
+        outpoint: OutPoint,
+    ) -> Result<(Transaction, TxSupplement), Self::Error>;
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+    use std::collections::BTreeMap;
+    use std::str::FromStr;
+
+    use bitcoin::hashes::{sha256, Hash};
+    use bitcoin::{secp256k1, Script, TxIn, TxOut, Txid};
+    use dbc::{ScriptEncodeData, ScriptEncodeMethod};
+    use single_use_seals::SealStatus;
+
+    use super::*;
+
+    const FEE: u64 = 1000;
+    const PROTOCOL_FACTOR: u32 = 7;
+
+    struct MockResolver {
+        chain: Option<Chain>,
+        host: Transaction,
+    }
+
+    impl MockResolver {
+        fn supplement() -> TxSupplement {
+            TxSupplement {
+                protocol_factor: PROTOCOL_FACTOR,
+                fee: FEE,
+                tag: sha256::Hash::hash(b"bp:seals:test"),
+            }
+        }
+    }
+
+    impl TxResolve for MockResolver {
+        type Error = Error;
+
+        fn chain(&self) -> Option<Chain> { self.chain }
+
+        fn tx_container(
+            &self,
+            _outpoint: OutPoint,
+        ) -> Result<TxContainer, Self::Error> {
+            let supplement = Self::supplement();
+            let pubkey = secp256k1::PublicKey::from_str(
+                "0218845781f631c48f1c9709e23092067d06837f30aa0cd0544ac887fe91ddd166",
+            )
+            .unwrap();
+            Ok(TxContainer::construct(
+                supplement.protocol_factor,
+                &supplement.tag,
+                supplement.fee,
+                self.host.clone(),
+                pubkey,
+                ScriptEncodeData::SinglePubkey,
+                ScriptEncodeMethod::WPubkeyHash,
+            ))
+        }
+
+        fn tx_and_data(
+            &self,
+            _outpoint: OutPoint,
+        ) -> Result<(Transaction, TxSupplement), Self::Error> {
+            Ok((self.host.clone(), Self::supplement()))
+        }
+    }
+
+    /// Resolver serving transactions only through [`AsyncTxResolve`]
+    #[cfg(feature = "async")]
+    struct AsyncResolver(MockResolver);
+
+    #[cfg(feature = "async")]
+    impl TxResolve for AsyncResolver {
+        type Error = Error;
+
+        fn tx_container(
+            &self,
+            _outpoint: OutPoint,
+        ) -> Result<TxContainer, Self::Error> {
+            Err(Error::ResolverError)
+        }
+
+        fn tx_and_data(
+            &self,
+            _outpoint: OutPoint,
+        ) -> Result<(Transaction, TxSupplement), Self::Error> {
+            Err(Error::ResolverError)
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[async_trait]
+    impl AsyncTxResolve for AsyncResolver {
+        type Error = Error;
+
+        fn chain(&self) -> Option<Chain> { self.0.chain }
+
+        async fn tx_container(
+            &self,
+            outpoint: OutPoint,
+        ) -> Result<TxContainer, Self::Error> {
+            TxResolve::tx_container(&self.0, outpoint)
+        }
+
+        async fn tx_and_data(
+            &self,
+            outpoint: OutPoint,
+        ) -> Result<(Transaction, TxSupplement), Self::Error> {
+            TxResolve::tx_and_data(&self.0, outpoint)
+        }
+    }
+
+    struct MockMedium;
+
+    impl<'a, R: TxResolve> SealMedium<TxoutSeal<'a, R>> for MockMedium {
+        type PublicationId = ();
+        type Error = Error;
+
+        fn define_seal(
+            &self,
+            _definition: &OutPoint,
+        ) -> Result<TxoutSeal<'a, R>, Self::Error> {
+            Err(Error::InvalidSealDefinition)
+        }
+
+        fn get_seal_status(
+            &self,
+            _seal: &TxoutSeal<'a, R>,
+        ) -> Result<SealStatus, Self::Error> {
+            Ok(SealStatus::Undefined)
+        }
+    }
+
+    fn seal_outpoint() -> OutPoint {
+        OutPoint::new(
+            Txid::from_str(
+                "646ca5c1062619e2a2d60771c9dfd820551fb773e4dc8c4ed67965a8d1fae839",
+            )
+            .unwrap(),
+            1,
+        )
+    }
+
+    fn host_tx(seal: OutPoint) -> Transaction {
+        Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![TxIn {
+                previous_output: seal,
+                ..TxIn::default()
+            }],
+            output: vec![
+                TxOut {
+                    value: 10_000,
+                    script_pubkey: Script::default(),
+                };
+                2
+            ],
+        }
+    }
+
+    /// Closes seal defined by `outpoint` over `msg` and returns the witness
+    /// together with the witness transaction
+    fn close_at(outpoint: OutPoint, msg: &Message) -> (Witness, Transaction) {
+        let resolver = MockResolver {
+            chain: None,
+            host: host_tx(outpoint),
+        };
+        let seal = TxoutSeal::new(outpoint, &resolver);
+        let witness = seal.close(msg).unwrap();
+        let host = witness.0.as_inner().clone();
+        (witness, host)
+    }
+
+    /// Closes the seal over `msg` and returns the witness together with the
+    /// resolver serving the witness transaction
+    fn close(chain: Option<Chain>, msg: &Message) -> (Witness, MockResolver) {
+        let (witness, host) = close_at(seal_outpoint(), msg);
+        (witness, MockResolver { chain, host })
+    }
+
+    #[test]
+    fn test_txout_seal_verify() {
+        let msg = Message::hash(b"message");
+        let (witness, resolver) = close(None, &msg);
+        let seal = TxoutSeal::new(seal_outpoint(), &resolver);
+        assert_eq!(seal.verify(&msg, &witness, &MockMedium), Ok(true));
+        assert_eq!(
+            seal.verify(
+                &Message::hash(b"other message"),
+                &witness,
+                &MockMedium
+            ),
+            Ok(false)
+        );
+
+        let resolver = MockResolver {
+            chain: None,
+            host: host_tx(seal_outpoint()),
+        };
+        let seal = TxoutSeal::new(seal_outpoint(), &resolver);
+        assert_eq!(seal.verify(&msg, &witness, &MockMedium), Ok(false));
+    }
+
+    #[test]
+    fn test_txout_seal_resolver_lying() {
+        let msg = Message::hash(b"message");
+        let (witness, mut resolver) = close(None, &msg);
+        resolver.host.input.clear();
+        let seal = TxoutSeal::new(seal_outpoint(), &resolver);
+        assert_eq!(
+            seal.verify(&msg, &witness, &MockMedium),
+            Err(Error::ResolverLying)
+        );
+    }
+
+    #[test]
+    fn test_txout_seal_chain() {
+        let msg = Message::hash(b"message");
+        let (witness, resolver) = close(Some(Chain::Signet), &msg);
+        let seal =
+            TxoutSeal::with_chain(seal_outpoint(), Chain::Signet, &resolver);
+        assert_eq!(seal.verify(&msg, &witness, &MockMedium), Ok(true));
+        assert!(seal.close(&msg).is_ok());
+
+        let seal =
+            TxoutSeal::with_chain(seal_outpoint(), Chain::Mainnet, &resolver);
+        assert_eq!(
+            seal.verify(&msg, &witness, &MockMedium),
+            Err(Error::ChainMismatch)
+        );
+        assert_eq!(seal.close(&msg).err(), Some(Error::ChainMismatch));
+
+        // Resolvers not reporting their chain can't be used with seals bound
+        // to a chain
+        let (witness, resolver) = close(None, &msg);
+        let seal =
+            TxoutSeal::with_chain(seal_outpoint(), Chain::Regtest, &resolver);
+        assert_eq!(
+            seal.verify(&msg, &witness, &MockMedium),
+            Err(Error::ChainMismatch)
+        );
+        assert_eq!(seal.close(&msg).err(), Some(Error::ChainMismatch));
+    }
+
+    #[test]
+    fn test_txout_seal_from_chain_seal() {
+        let msg = Message::hash(b"message");
+        let (witness, resolver) = close(Some(Chain::Testnet3), &msg);
+
+        let chain_seal =
+            ChainSeal::new(Chain::Testnet3, SealPoint::from(seal_outpoint()));
+        let seal = TxoutSeal::with_chain_seal(chain_seal, &resolver).unwrap();
+        assert_eq!(seal.verify(&msg, &witness, &MockMedium), Ok(true));
+
+        let chain_seal =
+            ChainSeal::new(Chain::Mainnet, SealPoint::from(seal_outpoint()));
+        let seal = TxoutSeal::with_chain_seal(chain_seal, &resolver).unwrap();
+        assert_eq!(
+            seal.verify(&msg, &witness, &MockMedium),
+            Err(Error::ChainMismatch)
+        );
+
+        let chain_seal =
+            ChainSeal::new(Chain::Testnet3, SealPoint::WitnessVout(1));
+        assert_eq!(
+            TxoutSeal::with_chain_seal(chain_seal, &resolver).err(),
+            Some(Error::InvalidSealDefinition)
+        );
+    }
+
+    struct BatchResolver {
+        hosts: BTreeMap<OutPoint, Transaction>,
+        batch_calls: Cell<usize>,
+    }
+
+    impl TxResolve for BatchResolver {
+        type Error = Error;
+
+        fn tx_container(
+            &self,
+            _outpoint: OutPoint,
+        ) -> Result<TxContainer, Self::Error> {
+            Err(Error::ResolverError)
+        }
+
+        fn tx_and_data(
+            &self,
+            outpoint: OutPoint,
+        ) -> Result<(Transaction, TxSupplement), Self::Error> {
+            let host = self.hosts.get(&outpoint).ok_or(Error::ResolverError)?;
+            Ok((host.clone(), MockResolver::supplement()))
+        }
+
+        fn tx_and_data_batch(
+            &self,
+            outpoints: &[OutPoint],
+        ) -> Result<Vec<(Transaction, TxSupplement)>, Self::Error> {
+            self.batch_calls.set(self.batch_calls.get() + 1);
+            outpoints
+                .iter()
+                .map(|outpoint| self.tx_and_data(*outpoint))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_txout_seal_verify_batch() {
+        let outpoint1 = seal_outpoint();
+        let outpoint2 = OutPoint::new(outpoint1.txid, 0);
+        let msg1 = Message::hash(b"first message");
+        let msg2 = Message::hash(b"second message");
+        let (witness1, host1) = close_at(outpoint1, &msg1);
+        let (witness2, host2) = close_at(outpoint2, &msg2);
+        let resolver = BatchResolver {
+            hosts: bmap! { outpoint1 => host1, outpoint2 => host2 },
+            batch_calls: Cell::new(0),
+        };
+
+        assert_eq!(
+            TxoutSeal::verify_batch(&resolver, None, &[
+                (outpoint1, &msg1, &witness1),
+                (outpoint2, &msg2, &witness2),
+                (outpoint1, &msg2, &witness1),
+            ]),
+            Ok(vec![true, true, false])
+        );
+        assert_eq!(
+            TxoutSeal::verify_batch(&resolver, None, &[
+                (outpoint2, &msg1, &witness2),
+                (outpoint1, &msg1, &witness1),
+            ]),
+            Ok(vec![false, true])
+        );
+        assert_eq!(resolver.batch_calls.get(), 2);
+
+        assert_eq!(TxoutSeal::verify_batch(&resolver, None, &[]), Ok(vec![]));
+
+        // Failure to resolve any of the witnesses fails the whole batch
+        let unknown = OutPoint::new(outpoint1.txid, 2);
+        assert_eq!(
+            TxoutSeal::verify_batch(&resolver, None, &[
+                (outpoint1, &msg1, &witness1),
+                (unknown, &msg1, &witness1),
+            ]),
+            Err(Error::ResolverError)
+        );
+        assert_eq!(
+            TxoutSeal::verify_batch(&resolver, Some(Chain::Mainnet), &[(
+                outpoint1, &msg1, &witness1
+            )]),
+            Err(Error::ChainMismatch)
+        );
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_txout_seal_verify_async() {
+        use futures::executor::block_on;
+
+        let msg = Message::hash(b"message");
+        let (witness, resolver) = close(None, &msg);
+        let resolver = AsyncResolver(resolver);
+        let seal = TxoutSeal::new(seal_outpoint(), &resolver);
+        assert_eq!(
+            block_on(seal.verify_witness_async(&msg, &witness)),
+            Ok(true)
+        );
+        assert_eq!(
+            block_on(seal.verify_witness_async(
+                &Message::hash(b"other message"),
+                &witness
+            )),
+            Ok(false)
+        );
+        // Blocking verification does not use the asynchronous resolver
+        assert_eq!(
+            seal.verify(&msg, &witness, &MockMedium),
+            Err(Error::ResolverError)
+        );
+
+        let (witness, resolver) = close(Some(Chain::Mainnet), &msg);
+        let resolver = AsyncResolver(resolver);
+        let seal =
+            TxoutSeal::with_chain(seal_outpoint(), Chain::Testnet3, &resolver);
+        assert_eq!(
+            block_on(seal.verify_witness_async(&msg, &witness)),
+            Err(Error::ChainMismatch)
+        );
+    }
 }
@@ -15,6 +15,11 @@
 
 use dbc::{Proof, TxCommitment};
 
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
 pub struct Witness(pub InnerWitness, pub OuterWitness);
 
 pub type InnerWitness = TxCommitment;
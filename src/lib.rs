@@ -34,6 +34,9 @@
 extern crate amplify;
 #[macro_use]
 extern crate strict_encoding;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde_crate as serde;
 
 pub extern crate dbc;
 pub extern crate seals;
@@ -50,8 +50,8 @@ pub struct BlockChecksum(u8);
 impl From<BlockHash> for BlockChecksum {
     fn from(block_hash: BlockHash) -> Self {
         let mut xor: u8 = 0;
-        for byte in block_hash.to_vec() {
-            xor ^= byte;
+        for byte in block_hash.iter() {
+            xor ^= *byte;
         }
         Self::from(xor)
     }
@@ -239,8 +239,8 @@ impl Descriptor {
                     block_checksum,
                     tx_index,
                 },
-                Some(dim),
-            ) if dim == Input => Ok(Descriptor::OnchainTxInput {
+                Some(Input),
+            ) => Ok(Descriptor::OnchainTxInput {
                 block_height,
                 block_checksum,
                 tx_index,
@@ -252,24 +252,20 @@ impl Descriptor {
                     block_checksum,
                     tx_index,
                 },
-                Some(dim),
-            ) if dim == Output => Ok(Descriptor::OnchainTxOutput {
+                Some(Output),
+            ) => Ok(Descriptor::OnchainTxOutput {
                 block_height,
                 block_checksum,
                 tx_index,
                 output_index: index,
             }),
-            (Descriptor::OffchainTransaction { tx_checksum }, Some(dim))
-                if dim == Input =>
-            {
+            (Descriptor::OffchainTransaction { tx_checksum }, Some(Input)) => {
                 Ok(Descriptor::OffchainTxInput {
                     tx_checksum,
                     input_index: index,
                 })
             }
-            (Descriptor::OffchainTransaction { tx_checksum }, Some(dim))
-                if dim == Output =>
-            {
+            (Descriptor::OffchainTransaction { tx_checksum }, Some(Output)) => {
                 Ok(Descriptor::OffchainTxOutput {
                     tx_checksum,
                     output_index: index,
@@ -411,6 +407,11 @@ impl Descriptor {
     StrictEncode,
     StrictDecode
 )]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
 #[display("{0:016X}")]
 pub struct ShortId(u64);
 
@@ -655,9 +656,8 @@ mod tests {
     fn short_id_get_descriptor_empty() {
         let sid = ShortId(0);
         let descriptor = sid.get_descriptor();
-        match descriptor.get_block_height() {
-            Some(h) => assert_eq!(h, 0),
-            None => {}
+        if let Some(h) = descriptor.get_block_height() {
+            assert_eq!(h, 0)
         }
     }
 
@@ -670,9 +670,8 @@ mod tests {
         ];
         for c in &test_cases {
             let sid = ShortId(c[0]);
-            match sid.get_descriptor().get_block_height() {
-                Some(h) => assert_eq!(u64::from(h), c[1]),
-                None => {}
+            if let Some(h) = sid.get_descriptor().get_block_height() {
+                assert_eq!(u64::from(h), c[1])
             }
         }
     }